@@ -1,28 +1,43 @@
 mod render;
+mod software;
 
-use crate::render::Render;
-use anyhow::Result;
+use crate::render::{Render, WHITE};
+use anyhow::{anyhow, Result};
 use miniquad::{
     conf::{Conf, Loading},
-    Context, EventHandler, UserData,
+    Context, EventHandler, MouseButton, UserData,
 };
+use std::{fs::File, io::Write};
 
 type Vec2 = vek::Vec2<f64>;
 
 const WIDTH: usize = 800;
 const HEIGHT: usize = 600;
 
+/// Maximum amount of times a single mesh can be drawn.
+const MAX_MESH_INSTANCES: usize = 1024 * 1024;
+
+/// How much a single scroll of the mouse wheel zooms in or out.
+const ZOOM_STEP: f32 = 1.1;
+
+/// Size of a single tile in the SVG.
+const TILE_SIZE: f64 = 30.0;
+
 /// Our app state.
 struct App {
     /// Our wrapper around the OpenGL calls.
     render: Render,
+    /// Last known position of the mouse on the screen.
+    mouse_pos: Vec2,
+    /// Whether the world is being dragged around with the mouse.
+    dragging: bool,
 }
 
 impl App {
     /// Setup the ECS and load the systems.
     pub fn new(ctx: &mut Context) -> Result<Self> {
         // Setup the OpenGL render part
-        let mut render = Render::new(ctx);
+        let mut render = Render::new(ctx, MAX_MESH_INSTANCES);
 
         // Use the default engine template
         let generator = siege::Generator::default();
@@ -33,14 +48,21 @@ impl App {
             .unwrap();
 
         // Convert it to a vector path
-        let svg = engine.to_svg(30.0);
+        let svg = engine.to_svg(TILE_SIZE);
 
         // Upload it to the GPU
         let logo_mesh = render.upload_svg(svg)?;
 
-        logo_mesh.add_instance(Vec2::zero());
+        // Draw it twice, the second one tinted blue to the right of the first
+        let svg_width = (engine.width() + 2) as f64 * TILE_SIZE;
+        logo_mesh.add_instance(Vec2::zero(), WHITE)?;
+        logo_mesh.add_instance(Vec2::new(svg_width, 0.0), [0.5, 0.5, 1.0, 1.0])?;
 
-        Ok(Self { render })
+        Ok(Self {
+            render,
+            mouse_pos: Vec2::zero(),
+            dragging: false,
+        })
     }
 }
 
@@ -51,9 +73,76 @@ impl EventHandler for App {
         // Render the buffer
         self.render.render(ctx);
     }
+
+    fn mouse_motion_event(&mut self, _ctx: &mut Context, x: f32, y: f32) {
+        let mouse_pos = Vec2::new(x as f64, y as f64);
+
+        if self.dragging {
+            self.render.camera.drag(mouse_pos - self.mouse_pos);
+        }
+
+        self.mouse_pos = mouse_pos;
+    }
+
+    fn mouse_wheel_event(&mut self, _ctx: &mut Context, _x: f32, y: f32) {
+        let factor = if y > 0.0 {
+            ZOOM_STEP
+        } else if y < 0.0 {
+            1.0 / ZOOM_STEP
+        } else {
+            return;
+        };
+
+        // Zoom in on the mouse cursor
+        self.render.camera.zoom_at(factor, self.mouse_pos);
+    }
+
+    fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        if button == MouseButton::Left {
+            self.dragging = true;
+            self.mouse_pos = Vec2::new(x as f64, y as f64);
+        }
+    }
+
+    fn mouse_button_up_event(&mut self, _ctx: &mut Context, button: MouseButton, _x: f32, _y: f32) {
+        if button == MouseButton::Left {
+            self.dragging = false;
+        }
+    }
+}
+
+/// Render a generated engine without a window and save it as a PAM image.
+fn render_to_file(path: &str) -> Result<()> {
+    let engine = siege::Generator::default()
+        .generate_skeleton(20, 20, 100, &mut rand::thread_rng())
+        .ok_or_else(|| anyhow!("Generating the engine failed"))?;
+    let svg = engine.to_svg(TILE_SIZE);
+
+    // The SVG is in pixels, so it can be rasterized directly
+    let (vertices, indices) = render::tessellate_svg(&svg, 1.0)?;
+    let width = ((engine.width() + 2) as f64 * TILE_SIZE) as usize;
+    let height = ((engine.height() + 2) as f64 * TILE_SIZE) as usize;
+    let pixels = software::rasterize(&vertices, &indices, width, height);
+
+    let mut file = File::create(path)?;
+    write!(
+        file,
+        "P7\nWIDTH {}\nHEIGHT {}\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n",
+        width, height
+    )?;
+    file.write_all(&pixels)?;
+
+    Ok(())
 }
 
 fn main() {
+    // Render without a window when a path to save the image to is passed
+    if let Some(path) = std::env::args().nth(1) {
+        render_to_file(&path).expect("Rendering to a file failed");
+
+        return;
+    }
+
     miniquad::start(
         Conf {
             window_title: concat!("siege lyon example - ", env!("CARGO_PKG_VERSION")).to_string(),
@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use lyon::{
     math::Point,
     path::PathEvent,
@@ -16,7 +16,11 @@ use std::{
 
 type Vec2 = vek::Vec2<f64>;
 
-const MAX_MESH_INSTANCES: usize = 1024 * 1024;
+/// Red, green, blue & alpha channels between `0.0` and `1.0`.
+pub type Color = [f32; 4];
+
+/// Color of an instance that doesn't change the colors of the mesh.
+pub const WHITE: Color = [1.0, 1.0, 1.0, 1.0];
 
 /// A reference to an uploaded vector path.
 ///
@@ -28,33 +32,61 @@ pub struct Mesh(Arc<Mutex<DrawCall>>);
 impl Mesh {
     /// Render an instance of this mesh.
     ///
-    /// Pretty slow because it needs to unlock the mutex. If possible use `draw_instances` instead.
-    pub fn add_instance(&self, pos: Vec2) {
+    /// The colors of the mesh are multiplied with the color, use `WHITE` to keep them as they
+    /// are. Pretty slow because it needs to unlock the mutex. If possible use `extend_instances`
+    /// instead.
+    ///
+    /// Fails when the mesh already has the maximum amount of instances.
+    pub fn add_instance(&self, pos: Vec2, color: Color) -> Result<()> {
         let mut dc = self.0.lock().unwrap();
 
-        dc.instances.push(Instance {
-            position: [pos.x as f32, pos.y as f32],
-        });
-        assert!(dc.instances.len() < MAX_MESH_INSTANCES);
+        dc.check_capacity(dc.instances.len() + 1)?;
+        dc.instances.push(Instance::new(pos, color));
 
         // Tell the render loop that the data is out of date
         dc.refresh_instances = true;
+
+        Ok(())
+    }
+
+    /// Render a list of extra instances of this mesh.
+    ///
+    /// The mutex is only locked once for all instances. The instances are uploaded to the GPU in
+    /// a single buffer update on the next frame, no matter how many times this is called.
+    ///
+    /// Fails without adding anything when the instances don't fit.
+    pub fn extend_instances(&self, instances: &[(Vec2, Color)]) -> Result<()> {
+        let mut dc = self.0.lock().unwrap();
+
+        dc.check_capacity(dc.instances.len() + instances.len())?;
+        dc.instances.extend(
+            instances
+                .iter()
+                .map(|(pos, color)| Instance::new(*pos, *color)),
+        );
+
+        // Tell the render loop that the data is out of date
+        dc.refresh_instances = true;
+
+        Ok(())
     }
 
     /// Render a list of instances of this mesh.
-    pub fn overwrite_instances(&self, pos: &Vec<Vec2>) {
+    ///
+    /// Fails without changing anything when the instances don't fit.
+    pub fn overwrite_instances(&self, instances: &[(Vec2, Color)]) -> Result<()> {
         let mut dc = self.0.lock().unwrap();
 
-        dc.instances = pos
+        dc.check_capacity(instances.len())?;
+        dc.instances = instances
             .iter()
-            .map(|pos| Instance {
-                position: [pos.x as f32, pos.y as f32],
-            })
+            .map(|(pos, color)| Instance::new(*pos, *color))
             .collect();
-        assert!(dc.instances.len() < MAX_MESH_INSTANCES);
 
         // Tell the render loop that the data is out of date
         dc.refresh_instances = true;
+
+        Ok(())
     }
 
     /// Remove all instances.
@@ -66,8 +98,130 @@ impl Mesh {
     }
 }
 
+/// The part of the world that's visible on the screen.
+#[derive(Debug, Copy, Clone)]
+pub struct Camera {
+    /// Amount of pixels a single world unit takes on the screen.
+    pub zoom: f32,
+    /// The world position shown at the top left of the screen.
+    pub pan: Vec2,
+}
+
+impl Camera {
+    /// Zoom in or out while keeping the world position below the screen position at the same
+    /// place.
+    pub fn zoom_at(&mut self, factor: f32, screen_pos: Vec2) {
+        let world_pos = self.to_world(screen_pos);
+
+        self.zoom *= factor;
+
+        // Move the world position back below the screen position
+        self.pan = world_pos - screen_pos / self.zoom as f64;
+    }
+
+    /// Move the camera by an amount of pixels on the screen, like dragging the world around.
+    pub fn drag(&mut self, screen_delta: Vec2) {
+        self.pan -= screen_delta / self.zoom as f64;
+    }
+
+    /// Convert a position on the screen to a position in the world.
+    pub fn to_world(&self, screen_pos: Vec2) -> Vec2 {
+        self.pan + screen_pos / self.zoom as f64
+    }
+
+    /// The part of the world that's on the screen with the screen size in pixels.
+    pub fn visible_bounds(&self, width: f32, height: f32) -> Bounds {
+        Bounds {
+            min: self.pan,
+            max: self.to_world(Vec2::new(width as f64, height as f64)),
+        }
+    }
+
+    /// The uniforms for the shader with the screen size in pixels.
+    fn uniforms(&self, width: f32, height: f32) -> Uniforms {
+        Uniforms {
+            zoom: (2.0 * self.zoom / width, 2.0 * self.zoom / height),
+            // The shader pans to the center of the screen
+            pan: (
+                -(self.pan.x as f32 + width / 2.0 / self.zoom),
+                -(self.pan.y as f32 + height / 2.0 / self.zoom),
+            ),
+        }
+    }
+}
+
+/// An axis aligned rectangle in the world.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Bounds {
+    /// The top left corner.
+    pub min: Vec2,
+    /// The bottom right corner.
+    pub max: Vec2,
+}
+
+impl Bounds {
+    /// The smallest rectangle containing all points, nothing overlaps it when there are none.
+    pub fn around<I>(points: I) -> Self
+    where
+        I: IntoIterator<Item = Vec2>,
+    {
+        points.into_iter().fold(
+            Self {
+                min: Vec2::broadcast(std::f64::INFINITY),
+                max: Vec2::broadcast(std::f64::NEG_INFINITY),
+            },
+            |bounds, point| Self {
+                min: Vec2::partial_min(bounds.min, point),
+                max: Vec2::partial_max(bounds.max, point),
+            },
+        )
+    }
+
+    /// The smallest rectangle containing all vertices.
+    fn around_vertices(vertices: &[Vertex]) -> Self {
+        Self::around(
+            vertices
+                .iter()
+                .map(|vertex| Vec2::new(vertex.pos[0] as f64, vertex.pos[1] as f64)),
+        )
+    }
+
+    /// Move the rectangle.
+    pub fn offset(self, offset: Vec2) -> Self {
+        Self {
+            min: self.min + offset,
+            max: self.max + offset,
+        }
+    }
+
+    /// Whether the rectangles share any area.
+    pub fn overlaps(&self, other: &Bounds) -> bool {
+        self.min.x < other.max.x
+            && other.min.x < self.max.x
+            && self.min.y < other.max.y
+            && other.min.y < self.max.y
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            pan: Vec2::zero(),
+        }
+    }
+}
+
 /// A wrapper around the OpenGL calls so the main file won't be polluted.
 pub struct Render {
+    /// The part of the world that's rendered.
+    pub camera: Camera,
+    /// The minimum width of strokes in pixels, so thin lines don't disappear.
+    ///
+    /// The strokes of uploaded SVGs are tessellated again when the zoom of the camera changes.
+    pub min_line_width: f32,
+    /// The maximum amount of instances for every mesh.
+    max_mesh_instances: usize,
     pipeline: Pipeline,
     /// A list of draw calls with bindings that will be generated.
     ///
@@ -76,11 +230,16 @@ pub struct Render {
     draw_calls: Vec<Arc<Mutex<DrawCall>>>,
     /// Whether some draw calls are missing bindings.
     missing_bindings: bool,
+    /// The part of the world for which the visible instances were uploaded.
+    culled_view: Option<Bounds>,
 }
 
 impl Render {
     /// Setup the OpenGL pipeline and the texture for the framebuffer.
-    pub fn new(ctx: &mut Context) -> Self {
+    ///
+    /// Every mesh can have at most `max_mesh_instances` instances, a buffer of this size is
+    /// allocated on the GPU for each mesh.
+    pub fn new(ctx: &mut Context, max_mesh_instances: usize) -> Self {
         // Create an OpenGL pipeline
         let shader = Shader::new(ctx, shader::VERTEX, shader::FRAGMENT, shader::META);
         let pipeline = Pipeline::new(
@@ -96,14 +255,19 @@ impl Render {
                 VertexAttribute::with_buffer("a_pos", VertexFormat::Float2, 0),
                 VertexAttribute::with_buffer("a_color", VertexFormat::Float4, 0),
                 VertexAttribute::with_buffer("a_inst_pos", VertexFormat::Float2, 1),
+                VertexAttribute::with_buffer("a_inst_color", VertexFormat::Float4, 1),
             ],
             shader,
         );
 
         Self {
+            camera: Camera::default(),
+            min_line_width: 1.0,
+            max_mesh_instances,
             pipeline,
             draw_calls: vec![],
             missing_bindings: false,
+            culled_view: None,
         }
     }
 
@@ -135,14 +299,11 @@ impl Render {
         let indices = geometry.indices.clone();
 
         // Create an OpenGL draw call for the path
-        let draw_call = Arc::new(Mutex::new(DrawCall {
+        let draw_call = Arc::new(Mutex::new(DrawCall::new(
             vertices,
             indices,
-            bindings: None,
-            instances: vec![],
-            instance_positions: vec![],
-            refresh_instances: false,
-        }));
+            self.max_mesh_instances,
+        )));
         self.draw_calls.push(draw_call.clone());
 
         // Tell the next render loop to create bindings for this
@@ -159,63 +320,18 @@ impl Render {
     where
         S: AsRef<str>,
     {
-        // Tessalate the path, converting it to vertices & indices
-        let mut geometry: VertexBuffers<Vertex, u16> = VertexBuffers::new();
+        // Convert the minimum width in pixels to the world
+        let min_line_width = self.min_line_width / self.camera.zoom;
 
-        let mut fill_tess = FillTessellator::new();
-        let mut stroke_tess = StrokeTessellator::new();
-
-        let rtree = usvg::Tree::from_str(svg.as_ref(), &usvg::Options::default())?;
-        // Loop over all nodes in the SVG tree
-        for node in rtree.root().descendants() {
-            if let usvg::NodeKind::Path(ref path) = *node.borrow() {
-                if let Some(ref fill) = path.fill {
-                    // Get the fill color
-                    let color = match fill.paint {
-                        usvg::Paint::Color(color) => color,
-                        _ => todo!("Color not defined"),
-                    };
-
-                    // Tessellate the fill
-                    fill_tess
-                        .tessellate(
-                            convert_path(path),
-                            &FillOptions::tolerance(0.01),
-                            &mut BuffersBuilder::new(
-                                &mut geometry,
-                                VertexCtor::new(color, fill.opacity.value() as f32),
-                            ),
-                        )
-                        .expect("Tessellation failed");
-                }
-
-                if let Some(ref stroke) = path.stroke {
-                    let (color, stroke_opts) = convert_stroke(stroke);
-                    // Tessellate the stroke
-                    let _ = stroke_tess.tessellate(
-                        convert_path(path),
-                        &stroke_opts.with_tolerance(0.01),
-                        &mut BuffersBuilder::new(
-                            &mut geometry,
-                            VertexCtor::new(color, stroke.opacity.value() as f32),
-                        ),
-                    );
-                }
-            }
-        }
-
-        let vertices = geometry.vertices.clone();
-        let indices = geometry.indices.clone();
+        let (vertices, indices) = tessellate_svg(svg.as_ref(), min_line_width)?;
 
         // Create an OpenGL draw call for the path
-        let draw_call = Arc::new(Mutex::new(DrawCall {
-            vertices,
-            indices,
-            bindings: None,
-            instances: vec![],
-            instance_positions: vec![],
-            refresh_instances: false,
-        }));
+        let mut draw_call = DrawCall::new(vertices, indices, self.max_mesh_instances);
+        // Keep the source so it can be tessellated again when zooming
+        draw_call.svg = Some(svg.as_ref().to_string());
+        draw_call.min_line_width = min_line_width;
+
+        let draw_call = Arc::new(Mutex::new(draw_call));
         self.draw_calls.push(draw_call.clone());
 
         // Tell the next render loop to create bindings for this
@@ -229,20 +345,34 @@ impl Render {
     pub fn render(&mut self, ctx: &mut Context) {
         let (width, height) = ctx.screen_size();
 
+        // All instances need to be culled again when the camera moved
+        let view = self.camera.visible_bounds(width, height);
+        let view_changed = self.culled_view != Some(view);
+        self.culled_view = Some(view);
+
+        // Convert the minimum width in pixels to the world
+        let min_line_width = self.min_line_width / self.camera.zoom;
+
         // Create bindings & update the instance vertices if necessary
         self.draw_calls.iter().for_each(|dc| {
             let mut dc = dc.lock().unwrap();
 
+            // Keep the strokes visible when zoomed out
+            dc.update_min_line_width(ctx, min_line_width)
+                .expect("Tessellating the SVG again failed");
+
             // Create bindings if missing
             if self.missing_bindings && dc.bindings.is_none() {
                 dc.create_bindings(ctx);
             }
 
-            if dc.refresh_instances {
-                // Upload the instance positions
+            if dc.refresh_instances || view_changed {
+                // Only upload the instances that are on the screen
+                let visible = visible_instances(&dc.instances, dc.bounds, view);
                 let bindings = dc.bindings.as_ref().unwrap();
-                bindings.vertex_buffers[1].update(ctx, &dc.instances);
+                bindings.vertex_buffers[1].update(ctx, &visible);
 
+                dc.visible_instances = visible.len();
                 dc.refresh_instances = false;
             }
         });
@@ -252,12 +382,14 @@ impl Render {
         // Start rendering
         ctx.begin_default_pass(PassAction::Nothing);
 
+        let uniforms = self.camera.uniforms(width, height);
+
         // Render the separate draw calls
         for dc in self.draw_calls.iter_mut() {
             let dc = dc.lock().unwrap();
 
-            // Only render when we actually have instances
-            if dc.instances.is_empty() {
+            // Only render when we actually have instances on the screen
+            if dc.visible_instances == 0 {
                 continue;
             }
 
@@ -266,11 +398,8 @@ impl Render {
             ctx.apply_pipeline(&self.pipeline);
             ctx.apply_scissor_rect(0, 0, width as i32, height as i32);
             ctx.apply_bindings(bindings);
-            ctx.apply_uniforms(&Uniforms {
-                zoom: (2.0 / width, 2.0 / height),
-                pan: (-width / 2.0, -height / 2.0),
-            });
-            ctx.draw(0, dc.indices.len() as i32, dc.instances.len() as i32);
+            ctx.apply_uniforms(&uniforms);
+            ctx.draw(0, dc.indices.len() as i32, dc.visible_instances as i32);
         }
 
         ctx.end_render_pass();
@@ -279,6 +408,55 @@ impl Render {
     }
 }
 
+/// Tessellate all fills & strokes of a SVG, converting it to vertices & indices.
+///
+/// Strokes are at least `min_line_width` wide, in the units of the SVG.
+pub fn tessellate_svg(svg: &str, min_line_width: f32) -> Result<(Vec<Vertex>, Vec<u16>)> {
+    let mut geometry: VertexBuffers<Vertex, u16> = VertexBuffers::new();
+
+    let mut fill_tess = FillTessellator::new();
+    let mut stroke_tess = StrokeTessellator::new();
+
+    let rtree = usvg::Tree::from_str(svg, &usvg::Options::default())?;
+    // Loop over all nodes in the SVG tree
+    for node in rtree.root().descendants() {
+        if let usvg::NodeKind::Path(ref path) = *node.borrow() {
+            if let Some(ref fill) = path.fill {
+                // Get the fill color
+                let color = paint_color(&rtree, &fill.paint)
+                    .ok_or_else(|| anyhow!("Color of fill not supported"))?;
+
+                // Tessellate the fill
+                fill_tess
+                    .tessellate(
+                        convert_path(path),
+                        &FillOptions::tolerance(0.01),
+                        &mut BuffersBuilder::new(
+                            &mut geometry,
+                            VertexCtor::new(color, fill.opacity.value() as f32),
+                        ),
+                    )
+                    .expect("Tessellation failed");
+            }
+
+            if let Some(ref stroke) = path.stroke {
+                let (color, stroke_opts) = convert_stroke(&rtree, stroke, min_line_width)?;
+                // Tessellate the stroke
+                let _ = stroke_tess.tessellate(
+                    convert_path(path),
+                    &stroke_opts.with_tolerance(0.01),
+                    &mut BuffersBuilder::new(
+                        &mut geometry,
+                        VertexCtor::new(color, stroke.opacity.value() as f32),
+                    ),
+                );
+            }
+        }
+    }
+
+    Ok((geometry.vertices, geometry.indices))
+}
+
 /// A single uploaded mesh as a draw call.
 #[derive(Debug)]
 struct DrawCall {
@@ -286,17 +464,85 @@ struct DrawCall {
     vertices: Vec<Vertex>,
     /// Render indices, build by lyon path.
     indices: Vec<u16>,
-    /// Position data for the instances.
-    instance_positions: Vec<[f32; 2]>,
     /// Render bindings, generated on render loop if empty.
     bindings: Option<Bindings>,
     /// List of instances to render.
     instances: Vec<Instance>,
     /// Whether the instance information should be reuploaded to the GPU.
     refresh_instances: bool,
+    /// Amount of instances that are uploaded to the GPU because they are on the screen.
+    visible_instances: usize,
+    /// Size of the instance buffer on the GPU.
+    max_instances: usize,
+    /// The area covered by the vertices.
+    bounds: Bounds,
+    /// The source of the vertices when they are tessellated from a SVG.
+    svg: Option<String>,
+    /// The minimum width of the strokes in the world the SVG is tessellated with.
+    min_line_width: f32,
 }
 
 impl DrawCall {
+    /// Create a draw call without instances for a tessellated path.
+    fn new(vertices: Vec<Vertex>, indices: Vec<u16>, max_instances: usize) -> Self {
+        let bounds = Bounds::around_vertices(&vertices);
+
+        Self {
+            vertices,
+            indices,
+            bindings: None,
+            instances: vec![],
+            refresh_instances: false,
+            visible_instances: 0,
+            max_instances,
+            bounds,
+            svg: None,
+            min_line_width: 0.0,
+        }
+    }
+
+    /// Tessellate the SVG again when the minimum width of the strokes changed.
+    fn update_min_line_width(&mut self, ctx: &mut Context, min_line_width: f32) -> Result<()> {
+        let svg = match &self.svg {
+            Some(svg) if self.min_line_width != min_line_width => svg,
+            _ => return Ok(()),
+        };
+
+        let (vertices, indices) = tessellate_svg(svg, min_line_width)?;
+        self.bounds = Bounds::around_vertices(&vertices);
+        self.vertices = vertices;
+        self.indices = indices;
+        self.min_line_width = min_line_width;
+
+        // Replace the buffers of the path, the instance buffer stays the same
+        if let Some(bindings) = self.bindings.as_mut() {
+            bindings.vertex_buffers[0].delete();
+            bindings.index_buffer.delete();
+
+            bindings.vertex_buffers[0] =
+                Buffer::immutable(ctx, BufferType::VertexBuffer, &self.vertices);
+            bindings.index_buffer = Buffer::immutable(ctx, BufferType::IndexBuffer, &self.indices);
+        }
+
+        // Different instances can be on the screen with the new bounds
+        self.refresh_instances = true;
+
+        Ok(())
+    }
+
+    /// Fail when the amount of instances doesn't fit in the buffer on the GPU.
+    fn check_capacity(&self, instances: usize) -> Result<()> {
+        if instances > self.max_instances {
+            Err(anyhow!(
+                "Mesh can't have {} instances, the maximum is {}",
+                instances,
+                self.max_instances
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
     /// Create bindings if they are missing.
     fn create_bindings(&mut self, ctx: &mut Context) {
         // The vertex buffer of the vector paths
@@ -304,15 +550,15 @@ impl DrawCall {
         // The index buffer of the vector paths
         let index_buffer = Buffer::immutable(ctx, BufferType::IndexBuffer, &self.indices);
 
-        // A dynamic buffer that will contain all positions for all instances
-        let instance_positions = Buffer::stream(
+        // A dynamic buffer that will contain the position & color of all instances
+        let instance_buffer = Buffer::stream(
             ctx,
             BufferType::VertexBuffer,
-            MAX_MESH_INSTANCES * mem::size_of::<Instance>(),
+            self.max_instances * mem::size_of::<Instance>(),
         );
 
         let bindings = Bindings {
-            vertex_buffers: vec![vertex_buffer, instance_positions],
+            vertex_buffers: vec![vertex_buffer, instance_buffer],
             index_buffer,
             images: vec![],
         };
@@ -320,11 +566,14 @@ impl DrawCall {
     }
 }
 
+/// A corner of a tessellated triangle.
 #[repr(C)]
 #[derive(Debug, Copy, Clone, Default)]
-struct Vertex {
-    pos: [f32; 2],
-    color: [f32; 4],
+pub struct Vertex {
+    /// Position in pixels.
+    pub pos: [f32; 2],
+    /// Color of the corner.
+    pub color: Color,
 }
 
 #[repr(C)]
@@ -335,14 +584,37 @@ struct Uniforms {
 }
 
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 struct Instance {
     position: [f32; 2],
+    color: Color,
+}
+
+impl Instance {
+    fn new(pos: Vec2, color: Color) -> Self {
+        Self {
+            position: [pos.x as f32, pos.y as f32],
+            color,
+        }
+    }
+}
+
+/// The instances of which the mesh is at least partially inside of the view.
+fn visible_instances(instances: &[Instance], mesh_bounds: Bounds, view: Bounds) -> Vec<Instance> {
+    instances
+        .iter()
+        .filter(|instance| {
+            let position = Vec2::new(instance.position[0] as f64, instance.position[1] as f64);
+
+            mesh_bounds.offset(position).overlaps(&view)
+        })
+        .copied()
+        .collect()
 }
 
 /// Used by lyon to create vertices.
 struct VertexCtor {
-    color: [f32; 4],
+    color: Color,
 }
 
 impl VertexCtor {
@@ -479,11 +751,38 @@ fn convert_path<'a>(p: &'a usvg::Path) -> PathConvIter<'a> {
     }
 }
 
-fn convert_stroke(s: &usvg::Stroke) -> (usvg::Color, StrokeOptions) {
-    let color = match s.paint {
-        usvg::Paint::Color(c) => c,
-        _ => todo!("No fallback color"),
-    };
+/// Get the color of a fill or a stroke.
+///
+/// Gradients are approximated with the color of their first stop, other paint servers aren't
+/// supported.
+fn paint_color(rtree: &usvg::Tree, paint: &usvg::Paint) -> Option<usvg::Color> {
+    match paint {
+        usvg::Paint::Color(color) => Some(*color),
+        usvg::Paint::Link(id) => {
+            let node = rtree.defs_by_id(id)?;
+            let color = match *node.borrow() {
+                usvg::NodeKind::LinearGradient(ref gradient) => {
+                    gradient.base.stops.first().map(|stop| stop.color)
+                }
+                usvg::NodeKind::RadialGradient(ref gradient) => {
+                    gradient.base.stops.first().map(|stop| stop.color)
+                }
+                _ => None,
+            };
+
+            color
+        }
+    }
+}
+
+/// Convert the stroke of a SVG path to lyon options, the width is at least `min_line_width`.
+fn convert_stroke(
+    rtree: &usvg::Tree,
+    s: &usvg::Stroke,
+    min_line_width: f32,
+) -> Result<(usvg::Color, StrokeOptions)> {
+    let color =
+        paint_color(rtree, &s.paint).ok_or_else(|| anyhow!("Color of stroke not supported"))?;
     let linecap = match s.linecap {
         usvg::LineCap::Butt => LineCap::Butt,
         usvg::LineCap::Square => LineCap::Square,
@@ -496,11 +795,11 @@ fn convert_stroke(s: &usvg::Stroke) -> (usvg::Color, StrokeOptions) {
     };
 
     let opt = StrokeOptions::tolerance(0.01)
-        .with_line_width(s.width.value() as f32)
+        .with_line_width((s.width.value() as f32).max(min_line_width))
         .with_line_cap(linecap)
         .with_line_join(linejoin);
 
-    (color, opt)
+    Ok((color, opt))
 }
 
 mod shader {
@@ -514,6 +813,7 @@ uniform vec2 u_pan;
 attribute vec2 a_pos;
 attribute vec4 a_color;
 attribute vec2 a_inst_pos;
+attribute vec4 a_inst_color;
 
 varying lowp vec4 color;
 
@@ -521,7 +821,7 @@ void main() {
     vec2 pos = a_pos + a_inst_pos + u_pan;
     gl_Position = vec4(pos * vec2(1.0, -1.0) * u_zoom, 0.0, 1.0);
 
-    color = a_color;
+    color = a_color * a_inst_color;
 }
 "#;
 
@@ -544,3 +844,136 @@ void main() {
         },
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extend_instances_test() -> Result<()> {
+        let mesh = Mesh(Arc::new(Mutex::new(DrawCall::new(vec![], vec![], 1024))));
+
+        let instances = (0..1000)
+            .map(|x| (Vec2::new(x as f64, 0.0), WHITE))
+            .collect::<Vec<_>>();
+        mesh.extend_instances(&instances)?;
+
+        let dc = mesh.0.lock().unwrap();
+        assert_eq!(dc.instances.len(), instances.len());
+        assert!(dc
+            .instances
+            .iter()
+            .zip(instances.iter())
+            .all(|(instance, (pos, _))| instance.position == [pos.x as f32, 0.0]));
+        // A single flag for all instances, so the render loop only updates the buffer once
+        assert!(dc.refresh_instances);
+
+        Ok(())
+    }
+
+    #[test]
+    fn gradient_test() -> Result<()> {
+        let svg = r##"<svg width="10" height="10" xmlns="http://www.w3.org/2000/svg">
+            <defs>
+                <linearGradient id="gradient">
+                    <stop offset="0" stop-color="#ff0000"/>
+                    <stop offset="1" stop-color="#0000ff"/>
+                </linearGradient>
+            </defs>
+            <rect width="10" height="10" fill="url(#gradient)"/>
+        </svg>"##;
+
+        let (vertices, indices) = tessellate_svg(svg, 1.0)?;
+        assert!(!indices.is_empty());
+        // The gradient is approximated with the color of it's first stop
+        assert!(vertices
+            .iter()
+            .all(|vertex| vertex.color == [1.0, 0.0, 0.0, 1.0]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn visible_instances_test() {
+        let mesh_bounds = Bounds {
+            min: Vec2::zero(),
+            max: Vec2::new(10.0, 10.0),
+        };
+        let view = Bounds {
+            min: Vec2::zero(),
+            max: Vec2::new(100.0, 100.0),
+        };
+
+        let instances = [
+            // Completely inside of the view
+            Instance::new(Vec2::new(50.0, 50.0), WHITE),
+            // Partially inside of the view
+            Instance::new(Vec2::new(-5.0, 95.0), WHITE),
+            // Left of the view
+            Instance::new(Vec2::new(-20.0, 50.0), WHITE),
+            // Below the view
+            Instance::new(Vec2::new(50.0, 100.0), WHITE),
+        ];
+
+        let visible = visible_instances(&instances, mesh_bounds, view);
+        assert_eq!(
+            visible
+                .iter()
+                .map(|instance| instance.position)
+                .collect::<Vec<_>>(),
+            vec![[50.0, 50.0], [-5.0, 95.0]]
+        );
+
+        // A mesh without vertices is never visible
+        assert!(visible_instances(&instances, Bounds::around(Vec::<Vec2>::new()), view).is_empty());
+    }
+    #[test]
+    fn min_line_width_test() -> Result<()> {
+        let svg = r##"<svg width="10" height="10" xmlns="http://www.w3.org/2000/svg">
+            <line x1="0" y1="5" x2="10" y2="5" stroke="#000000" stroke-width="0.1"/>
+        </svg>"##;
+
+        let line_width = |min_line_width| -> Result<f64> {
+            let (vertices, _) = tessellate_svg(svg, min_line_width)?;
+            let bounds = Bounds::around_vertices(&vertices);
+
+            Ok(bounds.max.y - bounds.min.y)
+        };
+
+        // Thin lines are widened
+        assert!((line_width(2.0)? - 2.0).abs() < 0.01);
+        // Lines that are wide enough stay the same
+        assert!((line_width(0.0)? - 0.1).abs() < 0.01);
+
+        Ok(())
+    }
+    #[test]
+    fn capacity_test() -> Result<()> {
+        let draw_call = DrawCall::new(vec![], vec![], 2);
+        assert!(draw_call.check_capacity(2).is_ok());
+        assert!(draw_call.check_capacity(3).is_err());
+
+        let mesh = Mesh(Arc::new(Mutex::new(draw_call)));
+        mesh.add_instance(Vec2::zero(), WHITE)?;
+        mesh.add_instance(Vec2::zero(), WHITE)?;
+        assert!(mesh.add_instance(Vec2::zero(), WHITE).is_err());
+        assert_eq!(mesh.0.lock().unwrap().instances.len(), 2);
+
+        // Nothing is added when not all instances fit
+        mesh.clear_instances();
+        mesh.add_instance(Vec2::zero(), WHITE)?;
+        assert!(mesh
+            .extend_instances(&[(Vec2::zero(), WHITE), (Vec2::zero(), WHITE)])
+            .is_err());
+        assert_eq!(mesh.0.lock().unwrap().instances.len(), 1);
+
+        // Overwriting replaces the instances, so they only have to fit on their own
+        mesh.overwrite_instances(&[(Vec2::zero(), WHITE), (Vec2::zero(), WHITE)])?;
+        assert!(mesh
+            .overwrite_instances(&[(Vec2::zero(), WHITE); 3])
+            .is_err());
+        assert_eq!(mesh.0.lock().unwrap().instances.len(), 2);
+
+        Ok(())
+    }
+}
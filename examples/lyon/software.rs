@@ -0,0 +1,96 @@
+use crate::render::Vertex;
+
+/// Fill the triangles of a tessellated path into a RGBA buffer without using the GPU.
+///
+/// The positions of the vertices are in pixels, triangles outside of the buffer are clipped. The
+/// colors of the corners are interpolated like the GPU renderer does and every pixel is
+/// overwritten without blending. The buffer has 4 bytes per pixel in rows of `width` pixels.
+pub fn rasterize(vertices: &[Vertex], indices: &[u16], width: usize, height: usize) -> Vec<u8> {
+    let mut buffer = vec![0; width * height * 4];
+
+    for triangle in indices.chunks_exact(3) {
+        let a = vertices[triangle[0] as usize];
+        let b = vertices[triangle[1] as usize];
+        let c = vertices[triangle[2] as usize];
+
+        // Skip triangles without an inside
+        let area = edge(a.pos, b.pos, c.pos);
+        if area == 0.0 {
+            continue;
+        }
+
+        // Only check the pixels in the bounding box of the triangle
+        let min_x = a.pos[0].min(b.pos[0]).min(c.pos[0]).floor().max(0.0) as usize;
+        let min_y = a.pos[1].min(b.pos[1]).min(c.pos[1]).floor().max(0.0) as usize;
+        let max_x = (a.pos[0].max(b.pos[0]).max(c.pos[0]).ceil().max(0.0) as usize).min(width);
+        let max_y = (a.pos[1].max(b.pos[1]).max(c.pos[1]).ceil().max(0.0) as usize).min(height);
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                // Sample the center of the pixel
+                let pos = [x as f32 + 0.5, y as f32 + 0.5];
+
+                // The barycentric weights, one of them is negative when the pixel is outside
+                let weight_a = edge(b.pos, c.pos, pos) / area;
+                let weight_b = edge(c.pos, a.pos, pos) / area;
+                let weight_c = edge(a.pos, b.pos, pos) / area;
+                if weight_a < 0.0 || weight_b < 0.0 || weight_c < 0.0 {
+                    continue;
+                }
+
+                let index = (y * width + x) * 4;
+                for (channel, value) in buffer[index..index + 4].iter_mut().enumerate() {
+                    let color = a.color[channel] * weight_a
+                        + b.color[channel] * weight_b
+                        + c.color[channel] * weight_c;
+
+                    *value = (color * 255.0).round().max(0.0).min(255.0) as u8;
+                }
+            }
+        }
+    }
+
+    buffer
+}
+
+/// Twice the signed area of the triangle, which tells on which side of the line from `start` to
+/// `end` the point is.
+fn edge(start: [f32; 2], end: [f32; 2], point: [f32; 2]) -> f32 {
+    (end[0] - start[0]) * (point[1] - start[1]) - (end[1] - start[1]) * (point[0] - start[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triangle_test() {
+        let red = [1.0, 0.0, 0.0, 1.0];
+        let vertices = [
+            Vertex {
+                pos: [0.0, 0.0],
+                color: red,
+            },
+            Vertex {
+                pos: [4.0, 0.0],
+                color: red,
+            },
+            Vertex {
+                pos: [0.0, 4.0],
+                color: red,
+            },
+        ];
+
+        let buffer = rasterize(&vertices, &[0, 1, 2], 4, 4);
+        assert_eq!(buffer.len(), 4 * 4 * 4);
+
+        let pixel = |x: usize, y: usize| &buffer[(y * 4 + x) * 4..(y * 4 + x) * 4 + 4];
+        // Inside of the triangle
+        assert_eq!(pixel(0, 0), &[255, 0, 0, 255]);
+        assert_eq!(pixel(1, 1), &[255, 0, 0, 255]);
+        assert_eq!(pixel(2, 0), &[255, 0, 0, 255]);
+        // Outside of the triangle
+        assert_eq!(pixel(3, 3), &[0, 0, 0, 0]);
+        assert_eq!(pixel(2, 2), &[0, 0, 0, 0]);
+    }
+}
@@ -21,6 +21,10 @@ struct Opt {
     /// Output height
     #[structopt(short, long, default_value = "10")]
     height: u32,
+
+    /// Directory to write a SVG file for every pattern learned from the input to
+    #[structopt(long, parse(from_os_str))]
+    dump_patterns: Option<std::path::PathBuf>,
 }
 
 fn main() {
@@ -29,6 +33,18 @@ fn main() {
     // Parse the ASCII file
     let generator = siege::Generator::from_ascii_file(opt.input).unwrap();
 
+    if let Some(dir) = &opt.dump_patterns {
+        std::fs::create_dir_all(dir).expect("Unable to create directory");
+
+        // Render every pattern as a tiny engine
+        let patterns = generator.patterns();
+        for (pattern_id, pattern) in patterns.iter().enumerate() {
+            let path = dir.join(format!("pattern_{}.svg", pattern_id));
+            std::fs::write(&path, pattern.to_svg(SVG_SCALE)).expect("Unable to write file");
+        }
+        println!("Written {} patterns to directory {:?}", patterns.len(), dir);
+    }
+
     // Generate a skeleton using WFC
     let engine = generator
         .generate_skeleton(opt.width, opt.height, 100, &mut rand::thread_rng())
@@ -0,0 +1,40 @@
+use crate::{Engine, Generator};
+use futures::channel::oneshot;
+use rand::Rng;
+use std::{future::Future, thread};
+
+impl Generator {
+    /// Generate a new skeleton on a separate thread.
+    ///
+    /// This does the same as [`generate_skeleton`] but doesn't block, the returned future
+    /// resolves when the worker thread is done. The generator is cloned so the future doesn't
+    /// borrow it.
+    ///
+    /// [`generate_skeleton`]: struct.Generator.html#method.generate_skeleton
+    pub fn generate_skeleton_async<R>(
+        &self,
+        output_width: u32,
+        output_height: u32,
+        retry_times: usize,
+        mut rng: R,
+    ) -> impl Future<Output = Option<Engine>>
+    where
+        R: Rng + Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+
+        let generator = self.clone();
+        thread::spawn(move || {
+            let engine =
+                generator.generate_skeleton(output_width, output_height, retry_times, &mut rng);
+
+            // The future might have been dropped already, in which case the result isn't needed
+            let _ = sender.send(engine);
+        });
+
+        async move {
+            // A panicking worker thread drops the sender, treat that as a failed generation
+            receiver.await.ok().and_then(|engine| engine)
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{Engine, Tile};
+
+/// Assemble an engine by hand from frames, beams & wheels.
+///
+/// Beams that cross other beams become a `Tile::Cross`, wheels are always placed on top of
+/// everything. Tiles outside of the engine are ignored.
+///
+/// ```rust
+/// let engine = siege::EngineBuilder::new(4, 3)
+///     .frame(0, 0, 4, 2)
+///     .wheel_at(0, 2)
+///     .wheel_at(3, 2)
+///     .build();
+///
+/// assert_eq!(engine.to_ascii(), "+--+\n+--+\no  o");
+/// ```
+#[derive(Debug, Clone)]
+pub struct EngineBuilder {
+    engine: Engine,
+}
+
+impl EngineBuilder {
+    /// Start with an empty engine.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            engine: Engine {
+                width,
+                height,
+                tiles: vec![Tile::Empty; width as usize * height as usize],
+            },
+        }
+    }
+
+    /// A rectangle of beams with crosses on the corners, `(x, y)` is the top left corner.
+    pub fn frame(self, x: u32, y: u32, width: u32, height: u32) -> Self {
+        if width == 0 || height == 0 {
+            return self;
+        }
+
+        let (right, bottom) = (x + width - 1, y + height - 1);
+
+        self.beam((x, y), (right, y))
+            .beam((x, bottom), (right, bottom))
+            .beam((x, y), (x, bottom))
+            .beam((right, y), (right, bottom))
+            .tile_at(x, y, Tile::Cross)
+            .tile_at(right, y, Tile::Cross)
+            .tile_at(x, bottom, Tile::Cross)
+            .tile_at(right, bottom, Tile::Cross)
+    }
+
+    /// Place a wheel.
+    pub fn wheel_at(self, x: u32, y: u32) -> Self {
+        self.tile_at(x, y, Tile::Wheel)
+    }
+
+    /// A straight beam between two tiles, including both ends.
+    ///
+    /// # Panics
+    ///
+    /// The beam must be horizontal, vertical or diagonal at exactly 45 degrees.
+    pub fn beam(mut self, from: (u32, u32), to: (u32, u32)) -> Self {
+        let dx = to.0 as i64 - from.0 as i64;
+        let dy = to.1 as i64 - from.1 as i64;
+
+        let tile = match (dx.signum(), dy.signum()) {
+            (0, _) => Tile::VerticalBeam,
+            (_, 0) => Tile::HorizontalBeam,
+            _ if dx.abs() != dy.abs() => panic!("beam is not straight or at 45 degrees"),
+            // Going down-right or up-left
+            (x, y) if x == y => Tile::DiagonalBeam2,
+            _ => Tile::DiagonalBeam1,
+        };
+
+        for step in 0..=dx.abs().max(dy.abs()) {
+            let x = from.0 as i64 + dx.signum() * step;
+            let y = from.1 as i64 + dy.signum() * step;
+
+            self.stamp(x as u32, y as u32, tile);
+        }
+
+        self
+    }
+
+    /// Crossed diagonal beams in a square, `(x, y)` is the top left corner.
+    pub fn diagonal_brace(self, x: u32, y: u32, size: u32) -> Self {
+        if size == 0 {
+            return self;
+        }
+
+        self.beam((x, y), (x + size - 1, y + size - 1))
+            .beam((x + size - 1, y), (x, y + size - 1))
+    }
+
+    /// Replace a single tile, ignoring what was there before.
+    pub fn tile_at(mut self, x: u32, y: u32, tile: Tile) -> Self {
+        if let Some(index) = self.engine.index(x, y) {
+            self.engine.tiles[index] = tile;
+        }
+
+        self
+    }
+
+    /// Create the engine.
+    pub fn build(self) -> Engine {
+        self.engine
+    }
+
+    /// Place a beam tile, turning it into a cross when it meets another beam.
+    fn stamp(&mut self, x: u32, y: u32, tile: Tile) {
+        let index = match self.engine.index(x, y) {
+            Some(index) => index,
+            None => return,
+        };
+
+        let existing = self.engine.tiles[index];
+        self.engine.tiles[index] = match existing {
+            Tile::Empty | Tile::Any | Tile::Wall => tile,
+            // Wheels & crosses already connect everything
+            Tile::Wheel | Tile::Cross | Tile::Joint => existing,
+            existing if existing == tile => tile,
+            _ => Tile::Cross,
+        };
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{Engine, Tile};
+use anyhow::{anyhow, Result};
+use num_traits::FromPrimitive;
+use std::convert::TryInto;
+
+/// Start of every serialized engine, the last byte is the version of the format.
+const MAGIC: [u8; 4] = *b"SGE\x01";
+/// Size of the magic, width & height before the tiles.
+const HEADER_SIZE: usize = 12;
+/// Starting value of the 64 bit FNV-1a hash.
+const FNV_OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+/// Multiplier of the 64 bit FNV-1a hash.
+const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+
+impl Engine {
+    /// Serialize the engine to a compact binary format.
+    ///
+    /// The format is a 4 byte magic header, the width & height as little endian `u32`s and then a
+    /// single byte per tile with it's numeric value. Use [`from_bytes`] to read it back.
+    ///
+    /// [`from_bytes`]: struct.Engine.html#method.from_bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + self.tiles.len());
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&self.width.to_le_bytes());
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        bytes.extend(self.tiles.iter().map(|tile| tile.as_u8()));
+
+        bytes
+    }
+
+    /// Deserialize an engine from the format written by [`to_bytes`].
+    ///
+    /// [`to_bytes`]: struct.Engine.html#method.to_bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < HEADER_SIZE || bytes[..4] != MAGIC {
+            return Err(anyhow!("bytes don't contain a serialized engine"));
+        }
+
+        let width = u32::from_le_bytes(bytes[4..8].try_into()?);
+        let height = u32::from_le_bytes(bytes[8..12].try_into()?);
+        let tiles = &bytes[HEADER_SIZE..];
+        if tiles.len() as u64 != width as u64 * height as u64 {
+            return Err(anyhow!(
+                "expected {} tiles for an engine of {}x{} but got {}",
+                width as u64 * height as u64,
+                width,
+                height,
+                tiles.len()
+            ));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            tiles: tiles_from_discriminants(tiles)?,
+        })
+    }
+
+    /// A 64 bit hash of the dimensions & the tiles.
+    ///
+    /// This uses FNV-1a over the same bytes as [`to_bytes`] writes after the magic, so the width,
+    /// height & tiles, which gives the same value on every platform and run, unlike the default
+    /// hasher of `HashMap`.
+    ///
+    /// [`to_bytes`]: struct.Engine.html#method.to_bytes
+    pub fn fingerprint(&self) -> u64 {
+        self.width
+            .to_le_bytes()
+            .iter()
+            .chain(self.height.to_le_bytes().iter())
+            .copied()
+            .chain(self.tiles.iter().map(|tile| tile.as_u8()))
+            .fold(FNV_OFFSET_BASIS, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+            })
+    }
+}
+
+/// Convert the numeric values of tiles back, the reserved `Edge` tile is not allowed.
+pub(crate) fn tiles_from_discriminants(discriminants: &[u8]) -> Result<Vec<Tile>> {
+    discriminants
+        .iter()
+        .map(|discriminant| match Tile::from_u8(*discriminant) {
+            Some(Tile::Edge) | None => Err(anyhow!(
+                "no tile with the number {} can be mapped",
+                discriminant
+            )),
+            Some(tile) => Ok(tile),
+        })
+        .collect()
+}
@@ -0,0 +1,90 @@
+use crate::{Engine, Tile};
+
+/// A drawing backend for [`Engine::render`].
+///
+/// All positions & sizes are in pixels, where `(0.0, 0.0)` is the top left of the engine. The tile
+/// that's being drawn is passed to every call so the backend can choose how to style it.
+///
+/// [`Engine::render`]: struct.Engine.html#method.render
+pub trait Canvas {
+    /// Fill a rectangle, with `(x, y)` as the top left corner.
+    fn fill_rect(&mut self, tile: Tile, x: f32, y: f32, width: f32, height: f32);
+
+    /// Draw a straight line with a thickness.
+    fn draw_line(&mut self, tile: Tile, start: (f32, f32), end: (f32, f32), thickness: f32);
+
+    /// Fill a circle.
+    fn draw_circle(&mut self, tile: Tile, center: (f32, f32), radius: f32);
+}
+
+impl Engine {
+    /// Draw the engine on a canvas, where every tile is `scale` by `scale` pixels.
+    ///
+    /// The walls are merged into as few rectangles as possible and drawn first, all other tiles
+    /// are drawn on top of them. The wheels are drawn last, see [`wheels`] for how neighboring
+    /// wheel tiles are merged.
+    ///
+    /// [`wheels`]: struct.Engine.html#method.wheels
+    pub fn render<C>(&self, canvas: &mut C, scale: f32)
+    where
+        C: Canvas,
+    {
+        // Beams are half a tile thick
+        self.render_with_thickness(canvas, scale, |_| 0.5)
+    }
+
+    /// Draw the engine on a canvas with the thickness of the beams as a fraction of a tile.
+    pub(crate) fn render_with_thickness<C, F>(&self, canvas: &mut C, scale: f32, thickness_of: F)
+    where
+        C: Canvas,
+        F: Fn(Tile) -> f32,
+    {
+        for (x, y, width, height) in self.wall_rects() {
+            canvas.fill_rect(
+                Tile::Wall,
+                x as f32 * scale,
+                y as f32 * scale,
+                width as f32 * scale,
+                height as f32 * scale,
+            );
+        }
+
+        for (index, tile) in self.tiles.iter().enumerate() {
+            let x = (index as u32 % self.width) as f32 * scale;
+            let y = (index as u32 / self.width) as f32 * scale;
+            let center = (x + scale / 2.0, y + scale / 2.0);
+
+            // The beams are centered in the tile
+            let size = thickness_of(*tile) * scale;
+            let offset = (scale - size) / 2.0;
+
+            match tile {
+                Tile::HorizontalBeam => canvas.fill_rect(*tile, x, y + offset, scale, size),
+                Tile::VerticalBeam => canvas.fill_rect(*tile, x + offset, y, size, scale),
+                Tile::DiagonalBeam1 => {
+                    canvas.draw_line(*tile, (x, y + scale), (x + scale, y), size)
+                }
+                Tile::DiagonalBeam2 => {
+                    canvas.draw_line(*tile, (x, y), (x + scale, y + scale), size)
+                }
+                Tile::Cross => {
+                    canvas.fill_rect(*tile, x + offset, y, size, scale);
+                    canvas.fill_rect(*tile, x, y + offset, scale, size);
+                }
+                Tile::Joint => {
+                    canvas.fill_rect(*tile, x + offset, y, size, scale);
+                    canvas.fill_rect(*tile, x, y + offset, scale, size);
+                    canvas.draw_circle(*tile, center, size / 4.0);
+                }
+                _ => (),
+            }
+        }
+
+        // Neighboring wheels are merged into bigger wheels
+        for wheel in self.wheels() {
+            let center = (wheel.center.0 * scale, wheel.center.1 * scale);
+
+            canvas.draw_circle(Tile::Wheel, center, wheel.radius * scale);
+        }
+    }
+}
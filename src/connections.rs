@@ -0,0 +1,136 @@
+use crate::{Engine, Tile};
+use coord_2d::Coord;
+use std::ops::{BitOr, BitOrAssign};
+
+/// A set of the eight directions a tile can connect to it's neighbors in.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Connections(u8);
+
+impl Connections {
+    /// No connections at all.
+    pub const NONE: Self = Self(0);
+    /// Connected to the tile above.
+    pub const NORTH: Self = Self(1);
+    /// Connected to the tile diagonally to the top right.
+    pub const NORTH_EAST: Self = Self(1 << 1);
+    /// Connected to the tile to the right.
+    pub const EAST: Self = Self(1 << 2);
+    /// Connected to the tile diagonally to the bottom right.
+    pub const SOUTH_EAST: Self = Self(1 << 3);
+    /// Connected to the tile below.
+    pub const SOUTH: Self = Self(1 << 4);
+    /// Connected to the tile diagonally to the bottom left.
+    pub const SOUTH_WEST: Self = Self(1 << 5);
+    /// Connected to the tile to the left.
+    pub const WEST: Self = Self(1 << 6);
+    /// Connected to the tile diagonally to the top left.
+    pub const NORTH_WEST: Self = Self(1 << 7);
+
+    /// Whether all connections of the other set are also in this set.
+    pub fn contains(self, other: Self) -> bool {
+        (self.0 & other.0) == other.0
+    }
+
+    /// Whether there are no connections.
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The set as bits, clockwise starting with north as the lowest bit.
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+}
+
+impl BitOr for Connections {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for Connections {
+    fn bitor_assign(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+}
+
+/// Every single direction with the offset to the neighbor in it.
+const DIRECTIONS: [(Connections, i32, i32); 8] = [
+    (Connections::NORTH, 0, -1),
+    (Connections::NORTH_EAST, 1, -1),
+    (Connections::EAST, 1, 0),
+    (Connections::SOUTH_EAST, 1, 1),
+    (Connections::SOUTH, 0, 1),
+    (Connections::SOUTH_WEST, -1, 1),
+    (Connections::WEST, -1, 0),
+    (Connections::NORTH_WEST, -1, -1),
+];
+
+impl Tile {
+    /// The directions the tile connects to it's neighbors in.
+    ///
+    /// Beams connect both ends, crosses & joints connect all four straight sides. Wheels and
+    /// walls are attached to whatever is next to them, so they don't have any connections of
+    /// their own.
+    pub fn connections(self) -> Connections {
+        match self {
+            Tile::HorizontalBeam => Connections::EAST | Connections::WEST,
+            Tile::VerticalBeam => Connections::NORTH | Connections::SOUTH,
+            Tile::DiagonalBeam1 => Connections::NORTH_EAST | Connections::SOUTH_WEST,
+            Tile::DiagonalBeam2 => Connections::NORTH_WEST | Connections::SOUTH_EAST,
+            Tile::Cross | Tile::Joint => {
+                Connections::NORTH | Connections::EAST | Connections::SOUTH | Connections::WEST
+            }
+            Tile::Empty | Tile::Any | Tile::Wheel | Tile::Wall | Tile::Edge => Connections::NONE,
+        }
+    }
+}
+
+impl Engine {
+    /// Find all beams with an end that doesn't connect to anything.
+    ///
+    /// Returns the position of every beam with the connections pointing at empty tiles or outside
+    /// of the engine. Crosses & joints are allowed to have open sides, like the corners of a frame,
+    /// so only the straight & diagonal beams are checked.
+    pub fn validate_connections(&self) -> Vec<(Coord, Connections)> {
+        self.tiles()
+            .iter()
+            .enumerate()
+            .filter(|(_, tile)| {
+                matches!(
+                    tile,
+                    Tile::HorizontalBeam
+                        | Tile::VerticalBeam
+                        | Tile::DiagonalBeam1
+                        | Tile::DiagonalBeam2
+                )
+            })
+            .filter_map(|(index, tile)| {
+                let x = (index % self.width() as usize) as i32;
+                let y = (index / self.width() as usize) as i32;
+
+                let dangling = DIRECTIONS
+                    .iter()
+                    .filter(|(direction, _, _)| tile.connections().contains(*direction))
+                    .filter(|(_, dx, dy)| {
+                        match self.index((x + dx) as u32, (y + dy) as u32) {
+                            Some(neighbor) => self.tiles()[neighbor].is_empty(),
+                            // Nothing outside of the engine to connect to
+                            None => true,
+                        }
+                    })
+                    .fold(Connections::NONE, |dangling, (direction, _, _)| {
+                        dangling | *direction
+                    });
+
+                if dangling.is_empty() {
+                    None
+                } else {
+                    Some((Coord::new(x, y), dangling))
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,225 @@
+use crate::{Engine, Tile};
+use std::collections::HashMap;
+
+/// The kind of structural joint a node in the graph represents.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum NodeKind {
+    /// A cross section connecting beams.
+    Cross,
+    /// A wheel.
+    Wheel,
+    /// The loose end of a beam that isn't connected to anything.
+    End,
+}
+
+/// A joint in the beam connectivity graph.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct GraphNode {
+    /// Horizontal tile position in the engine.
+    pub x: u32,
+    /// Vertical tile position in the engine.
+    pub y: u32,
+    /// What kind of joint this node is.
+    pub kind: NodeKind,
+}
+
+/// A connection between two nodes in the beam connectivity graph.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct GraphEdge {
+    /// Index of the first node.
+    pub from: usize,
+    /// Index of the second node.
+    pub to: usize,
+    /// The beam tile the connection consists of, `Cross` or `Wheel` when two nodes touch directly.
+    pub tile: Tile,
+    /// Amount of beam tiles in between the nodes.
+    pub length: u32,
+}
+
+/// Graph of how the structural members of an engine are connected.
+///
+/// Crosses & wheels become nodes, beam runs become edges between them. A beam run that doesn't end
+/// at a cross or a wheel gets a node of the `End` kind at it's last tile.
+#[derive(Debug, Clone, Default)]
+pub struct EngineGraph {
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+}
+
+impl EngineGraph {
+    /// Build the connectivity graph of an engine.
+    pub fn from_engine(engine: &Engine) -> Self {
+        let mut graph = Self::default();
+        let mut node_indices = HashMap::new();
+
+        let width = engine.width() as i32;
+        let height = engine.height() as i32;
+        let tile_at = |x: i32, y: i32| {
+            if x < 0 || y < 0 {
+                return Tile::Empty;
+            }
+
+            engine
+                .index(x as u32, y as u32)
+                .map_or(Tile::Empty, |index| engine.tiles()[index])
+        };
+
+        // All crosses & wheels are nodes
+        for y in 0..height {
+            for x in 0..width {
+                if let Some(kind) = node_kind(tile_at(x, y)) {
+                    graph.node_index(&mut node_indices, x, y, kind);
+                }
+            }
+        }
+
+        // Walk all beam runs
+        for &(beam, dx, dy) in BEAM_DIRECTIONS.iter() {
+            for y in 0..height {
+                for x in 0..width {
+                    // Only start at the first tile of a run
+                    if tile_at(x, y) != beam || tile_at(x - dx, y - dy) == beam {
+                        continue;
+                    }
+
+                    // Find the last tile of the run
+                    let mut length = 1;
+                    while tile_at(x + dx * length, y + dy * length) == beam {
+                        length += 1;
+                    }
+                    let (end_x, end_y) = (x + dx * (length - 1), y + dy * (length - 1));
+
+                    let from = graph.run_end_index(&mut node_indices, &tile_at, (x, y), (-dx, -dy));
+                    let to =
+                        graph.run_end_index(&mut node_indices, &tile_at, (end_x, end_y), (dx, dy));
+
+                    graph.edges.push(GraphEdge {
+                        from,
+                        to,
+                        tile: beam,
+                        length: length as u32,
+                    });
+                }
+            }
+        }
+
+        // Nodes directly next to each other are also connected
+        for node_index in 0..graph.nodes.len() {
+            let node = graph.nodes[node_index];
+            if node.kind == NodeKind::End {
+                continue;
+            }
+
+            for &(dx, dy) in [(1, 0), (0, 1)].iter() {
+                let (x, y) = (node.x as i32 + dx, node.y as i32 + dy);
+                if let Some(kind) = node_kind(tile_at(x, y)) {
+                    let to = graph.node_index(&mut node_indices, x, y, kind);
+                    graph.edges.push(GraphEdge {
+                        from: node_index,
+                        to,
+                        tile: tile_at(x, y),
+                        length: 0,
+                    });
+                }
+            }
+        }
+
+        graph
+    }
+
+    /// All joints in the graph.
+    pub fn nodes(&self) -> &Vec<GraphNode> {
+        &self.nodes
+    }
+
+    /// All connections between the joints, referring to indices in [`nodes`].
+    ///
+    /// [`nodes`]: struct.EngineGraph.html#method.nodes
+    pub fn edges(&self) -> &Vec<GraphEdge> {
+        &self.edges
+    }
+
+    /// Render the graph in the Graphviz DOT format.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph engine {\n");
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            let (name, shape) = match node.kind {
+                NodeKind::Cross => ("cross", "box"),
+                NodeKind::Wheel => ("wheel", "circle"),
+                NodeKind::End => ("end", "point"),
+            };
+            dot.push_str(&format!(
+                "    n{} [label=\"{} ({}, {})\", shape={}];\n",
+                index, name, node.x, node.y, shape
+            ));
+        }
+
+        for edge in self.edges.iter() {
+            dot.push_str(&format!(
+                "    n{} -- n{} [label=\"{}\"];\n",
+                edge.from, edge.to, edge.length
+            ));
+        }
+
+        dot.push('}');
+
+        dot
+    }
+
+    /// Get the index of the node at the position, creating it if it doesn't exist yet.
+    fn node_index(
+        &mut self,
+        node_indices: &mut HashMap<(i32, i32), usize>,
+        x: i32,
+        y: i32,
+        kind: NodeKind,
+    ) -> usize {
+        let nodes = &mut self.nodes;
+
+        *node_indices.entry((x, y)).or_insert_with(|| {
+            nodes.push(GraphNode {
+                x: x as u32,
+                y: y as u32,
+                kind,
+            });
+
+            nodes.len() - 1
+        })
+    }
+
+    /// Get the node at the end of a beam run, which is either the joint it connects to or a loose
+    /// end.
+    fn run_end_index<F>(
+        &mut self,
+        node_indices: &mut HashMap<(i32, i32), usize>,
+        tile_at: &F,
+        (x, y): (i32, i32),
+        (dx, dy): (i32, i32),
+    ) -> usize
+    where
+        F: Fn(i32, i32) -> Tile,
+    {
+        match node_kind(tile_at(x + dx, y + dy)) {
+            Some(kind) => self.node_index(node_indices, x + dx, y + dy, kind),
+            None => self.node_index(node_indices, x, y, NodeKind::End),
+        }
+    }
+}
+
+/// The beam tiles with the direction they run in.
+const BEAM_DIRECTIONS: [(Tile, i32, i32); 4] = [
+    (Tile::HorizontalBeam, 1, 0),
+    (Tile::VerticalBeam, 0, 1),
+    (Tile::DiagonalBeam1, 1, -1),
+    (Tile::DiagonalBeam2, 1, 1),
+];
+
+/// The kind of node a tile would be, if any.
+fn node_kind(tile: Tile) -> Option<NodeKind> {
+    match tile {
+        Tile::Cross | Tile::Joint => Some(NodeKind::Cross),
+        Tile::Wheel => Some(NodeKind::Wheel),
+        _ => None,
+    }
+}
@@ -5,6 +5,16 @@
 //! The `physics` & `vector` features are enabled by default and can be disabled if no dependencies
 //! on `lyon` or `nphysics2d` are warranted.
 //!
+//! The `image` feature adds rendering engines to pixel images & reading templates from them using
+//! the `image` crate.
+//!
+//! The `wasm` feature adds conversions from and to flat arrays that can be passed to JavaScript.
+//!
+//! The `async` feature adds generation on a worker thread that can be awaited.
+//!
+//! Loading templates & generating engines is logged with the `log` crate, at the `debug` level
+//! for results and the `trace` level for every attempt.
+//!
 //! # Examples
 //!
 //! ## ASCII
@@ -63,30 +73,82 @@
 //! # Ok(()) }
 //! ```
 
-use anyhow::{anyhow, Error, Result};
+#[cfg(feature = "async")]
+mod background;
+mod builder;
+mod bytes;
+mod canvas;
+mod connections;
+mod graph;
+mod material;
+mod patterns;
+#[cfg(feature = "image")]
+mod raster;
+mod resolver;
+mod steps;
+mod theme;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+pub use crate::{
+    builder::EngineBuilder,
+    canvas::Canvas,
+    connections::Connections,
+    graph::{EngineGraph, GraphEdge, GraphNode, NodeKind},
+    material::Material,
+    patterns::Direction,
+    resolver::{AnyResolver, DefaultAnyResolver},
+    steps::CollapseIterator,
+    theme::{Rgba, TileStyle, TileTheme, TRANSPARENT},
+};
+
+use anyhow::{anyhow, Context, Error, Result};
 use coord_2d::{Coord, Size};
 use grid_2d::Grid;
-use itertools::Itertools;
+use log::{debug, log_enabled, trace, Level};
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
-use rand::Rng;
+use rand::{rngs::StdRng, seq::index, Rng, SeedableRng};
 use simplesvg::{Attr, ColorAttr::Color, Fig, Svg};
 use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
     fs::File,
-    io::Read,
+    io::{self, Read, Write},
     num::NonZeroU32,
     path::Path,
     str::{self, FromStr},
+    sync::Arc,
+    time::{Duration, Instant},
 };
 use wfc::{
-    overlapping::OverlappingPatterns, retry::NumTimes, ForbidInterface, ForbidPattern, PatternId,
-    RunOwn, Wrap,
+    overlapping::OverlappingPatterns,
+    wrap::{WrapNone, WrapX, WrapXY},
+    ChosenPatternIdError, EnumerateCompatiblePatternWeights, ForbidInterface, ForbidPattern,
+    GlobalStats, Observe, PatternId, PropagateError, RunOwn, Wave, WaveCellRef, Wrap,
 };
 
 const PATTERN_SIZE: i32 = 3;
 
+/// Relative positions of all eight neighbors of a tile.
+const NEIGHBOR_OFFSETS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
 /// A generated siege engine.
-#[derive(Debug)]
+///
+/// The default engine is empty, which can be used as a buffer for
+/// [`Generator::generate_skeleton_into`].
+///
+/// [`Generator::generate_skeleton_into`]: struct.Generator.html#method.generate_skeleton_into
+#[derive(Debug, Clone, Default)]
 pub struct Engine {
     width: u32,
     height: u32,
@@ -94,482 +156,5025 @@ pub struct Engine {
 }
 
 impl Engine {
+    /// Wrap a grid of tiles in an engine without generating anything.
+    ///
+    /// The grid can't contain any `Tile::Edge` tiles.
+    pub fn from_grid(grid: &Grid<Tile>) -> Result<Self> {
+        if grid.iter().any(|tile| *tile == Tile::Edge) {
+            return Err(anyhow!("grid contains an edge tile, which is reserved"));
+        }
+
+        Ok(Self {
+            width: grid.width(),
+            height: grid.height(),
+            tiles: grid.iter().copied().collect(),
+        })
+    }
+
     /// Render the engine as ASCII art.
     pub fn to_ascii(&self) -> String {
+        let mut ascii = vec![];
+        self.write_ascii(&mut ascii)
+            .expect("writing to a vector can't fail");
+
+        String::from_utf8(ascii).expect("ASCII characters are always valid UTF-8")
+    }
+
+    /// Render the engine as text using Unicode box-drawing characters for the beams.
+    pub fn to_unicode(&self) -> String {
         self.tiles
-            .iter()
             .chunks(self.width as usize)
-            .into_iter()
-            .map(|row| row.map(|tile| tile.to_ascii()).collect::<String>())
+            .map(|row| row.iter().map(|tile| tile.to_unicode()).collect::<String>())
+            .collect::<Vec<_>>()
             .join("\n")
     }
 
+    /// Write the engine as ASCII art row by row, without building the whole string in memory.
+    pub fn write_ascii<W>(&self, w: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        for (index, row) in self.ascii_rows().enumerate() {
+            if index > 0 {
+                w.write_all(b"\n")?;
+            }
+
+            w.write_all(row.as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Convert the engine to ASCII one row at a time, without newlines.
+    pub fn ascii_rows(&self) -> impl Iterator<Item = String> + '_ {
+        self.tiles
+            .chunks(self.width as usize)
+            .map(|row| row.iter().map(|tile| tile.to_ascii()).collect())
+    }
+
     /// Render the engine as an SVG image.
     pub fn to_svg(&self, scale: f32) -> String {
-        let background_attr = Attr::default().fill(Color(0x9B, 0x4C, 0x51));
+        self.to_svg_with_theme(scale, &TileTheme::default())
+    }
 
-        let beam_attr = Attr::default()
-            .fill(Color(0x9B, 0x4C, 0x51))
-            .stroke(Color(0x52, 0x3B, 0x40))
-            .stroke_width(100.0 / scale);
+    /// Render the engine as an SVG image with custom colors.
+    ///
+    /// SVG shapes are always filled, so tiles with a transparent fill color aren't drawn. The
+    /// alpha channel of other colors is ignored. Diagonal beams are drawn as lines as thick as the
+    /// beam, so they're a single color without an outline: the stroke color when it isn't
+    /// transparent and the fill color otherwise.
+    pub fn to_svg_with_theme(&self, scale: f32, theme: &TileTheme) -> String {
+        // Beams are half a tile thick
+        self.svg(scale, theme, |_| 0.5)
+    }
 
-        // Convert the grid of tiles to SVG shapes
-        let figures = self
-            .to_grid()
-            .enumerate()
-            .map(|(coord, tile)| {
-                let x = coord.x as f32 * scale + scale;
-                let y = coord.y as f32 * scale + scale;
+    /// Render the engine as an SVG image where beams are as thick as their material.
+    ///
+    /// The outlines are scaled with the thickness, so wooden beams look the same as with
+    /// [`to_svg_with_theme`].
+    ///
+    /// [`to_svg_with_theme`]: struct.Engine.html#method.to_svg_with_theme
+    pub fn to_svg_with_materials<F>(&self, scale: f32, theme: &TileTheme, material_of: F) -> String
+    where
+        F: Fn(Tile) -> Material,
+    {
+        self.svg(scale, theme, |tile| material_of(tile).thickness())
+    }
 
-                match tile {
-                    Tile::Wall => {
-                        vec![Fig::Rect(x, y, scale, scale).styled(background_attr.clone())]
-                    }
-                    Tile::HorizontalBeam => {
-                        vec![Fig::Rect(x, y + scale / 4.0, scale, scale / 2.0)
-                            .styled(beam_attr.clone())]
-                    }
-                    Tile::VerticalBeam => {
-                        vec![Fig::Rect(x + scale / 4.0, y, scale / 2.0, scale)
-                            .styled(beam_attr.clone())]
-                    }
-                    Tile::Cross => vec![Fig::Multiple(vec![
-                        Fig::Rect(x + scale / 4.0, y, scale / 2.0, scale),
-                        Fig::Rect(x, y + scale / 4.0, scale, scale / 2.0),
-                    ])
-                    .styled(beam_attr.clone())],
-                    Tile::Wheel => vec![Fig::Circle(x + scale / 2.0, y + scale / 2.0, scale / 2.0)
-                        .styled(beam_attr.clone())],
-                    _ => vec![],
-                }
-            })
-            .flatten()
-            .collect();
+    /// Render the engine as an SVG image with the thickness of the beams as a fraction of a tile.
+    fn svg<F>(&self, scale: f32, theme: &TileTheme, thickness_of: F) -> String
+    where
+        F: Fn(Tile) -> f32,
+    {
+        let mut canvas = SvgCanvas {
+            figs: vec![],
+            // Leave a margin of a single tile around the engine
+            offset: scale,
+            scale,
+            theme,
+            thickness_of: &thickness_of,
+        };
+        self.render_with_thickness(&mut canvas, scale, &thickness_of);
 
         Svg(
-            figures,
+            canvas.figs,
             ((self.width + 2) as f32 * scale) as u32,
             ((self.height + 2) as f32 * scale) as u32,
         )
         .to_string()
     }
 
+    /// Cover all wall tiles with as few rectangles as possible.
+    ///
+    /// The rectangles are `(x, y, width, height)` tuples in tiles. This is greedy so the result is
+    /// not always optimal.
+    fn wall_rects(&self) -> Vec<(u32, u32, u32, u32)> {
+        let width = self.width as usize;
+        let mut covered = vec![false; self.tiles.len()];
+        let is_free_wall =
+            |covered: &[bool], index: usize| self.tiles[index] == Tile::Wall && !covered[index];
+
+        let mut rects = vec![];
+        for index in 0..self.tiles.len() {
+            if !is_free_wall(&covered, index) {
+                continue;
+            }
+            let (x, y) = (index % width, index / width);
+
+            // Grow to the right as far as possible
+            let mut rect_width = 1;
+            while x + rect_width < width && is_free_wall(&covered, index + rect_width) {
+                rect_width += 1;
+            }
+
+            // Grow down as long as the whole row below is free
+            let mut rect_height = 1;
+            while y + rect_height < self.height as usize
+                && (0..rect_width)
+                    .all(|dx| is_free_wall(&covered, index + rect_height * width + dx))
+            {
+                rect_height += 1;
+            }
+
+            for dy in 0..rect_height {
+                for dx in 0..rect_width {
+                    covered[index + dy * width + dx] = true;
+                }
+            }
+
+            rects.push((x as u32, y as u32, rect_width as u32, rect_height as u32));
+        }
+
+        rects
+    }
+
     /// The tiles array as a grid.
     pub fn to_grid(&self) -> Grid<Tile> {
         Grid::new_fn(Size::new(self.width, self.height), |coord| {
-            self.tiles[(coord.y * (self.width as i32) + coord.x) as usize]
+            let index = self
+                .index(coord.x as u32, coord.y as u32)
+                .expect("grid coordinate is outside of the engine");
+
+            self.tiles[index]
         })
     }
 
-    /// The array tiles as a one dimensional vector.
-    pub fn tiles(&self) -> &Vec<Tile> {
-        &self.tiles
+    /// The positions where the tiles differ from another engine, with the tile of both engines.
+    ///
+    /// Every item is a `(x, y, tile, other_tile)` tuple. Returns `None` when the engines don't have
+    /// the same dimensions.
+    pub fn diff(&self, other: &Engine) -> Option<Vec<(u32, u32, Tile, Tile)>> {
+        if self.width != other.width || self.height != other.height {
+            return None;
+        }
+
+        Some(
+            self.tiles
+                .iter()
+                .zip(other.tiles.iter())
+                .enumerate()
+                .filter(|(_, (tile, other_tile))| tile != other_tile)
+                .map(|(index, (tile, other_tile))| {
+                    (
+                        index as u32 % self.width,
+                        index as u32 / self.width,
+                        *tile,
+                        *other_tile,
+                    )
+                })
+                .collect(),
+        )
     }
 
-    /// Amount of tiles in a row.
-    pub fn width(&self) -> u32 {
-        self.width
+    /// The tiles as a vector of rows, so a tile can be accessed with `rows[y][x]`.
+    pub fn to_rows(&self) -> Vec<Vec<Tile>> {
+        self.tiles
+            .chunks(self.width as usize)
+            .map(|row| row.to_vec())
+            .collect()
     }
 
-    /// Amount of tiles in a column.
-    pub fn height(&self) -> u32 {
-        self.height
+    /// The index of a position in the tiles vector, `None` when it's outside of the engine.
+    ///
+    /// The math is done with checked `usize` arithmetic so big engines can't overflow.
+    pub(crate) fn index(&self, x: u32, y: u32) -> Option<usize> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        (y as usize)
+            .checked_mul(self.width as usize)?
+            .checked_add(x as usize)
+            .filter(|index| *index < self.tiles.len())
     }
-}
 
-/// Grid section of the siege engine.
-///
-/// This enum can be mapped to an ASCII character.
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, FromPrimitive)]
-pub enum Tile {
-    /// `' '` ASCII: empty space.
-    Empty,
-    /// `'*'` ASCII: any type, will be automatically filled following an edge detection heuristic.
-    Any,
-    /// `'o'` ASCII: a single wheel.
-    Wheel,
-    /// `'-'` ASCII: a beam connecting the tile above and the tile below.
-    HorizontalBeam,
-    /// `'|'` ASCII: a beam connecting the tile left and the tile right.
-    VerticalBeam,
-    /// `'/'` ASCII: a beam connecting connecting left bottom to right top.
-    DiagonalBeam1,
-    /// `'\'` ASCII: a beam connecting connecting left top to right bottom.
-    DiagonalBeam2,
-    /// `'+'` ASCII: a cross section connecting beams.
-    Cross,
-    /// `'.'` ASCII: a wall tile in between beams.
-    Wall,
+    /// Calculate the center of mass in tile units, where every tile has it's mass at it's center.
+    ///
+    /// The mass of each non-empty tile is determined by the passed function. When the total mass
+    /// is zero the geometric middle of the engine is returned.
+    pub fn center_of_mass<F>(&self, mass_of: F) -> (f32, f32)
+    where
+        F: Fn(Tile) -> f32,
+    {
+        let (total_mass, x_sum, y_sum) = self
+            .to_grid()
+            .enumerate()
+            .filter(|(_, tile)| !tile.is_empty())
+            .fold(
+                (0.0, 0.0, 0.0),
+                |(total_mass, x_sum, y_sum), (coord, tile)| {
+                    let mass = mass_of(*tile);
 
-    /// Edge tile, used internally.
-    #[doc(hidden)]
-    Edge = 255,
-}
+                    (
+                        total_mass + mass,
+                        x_sum + (coord.x as f32 + 0.5) * mass,
+                        y_sum + (coord.y as f32 + 0.5) * mass,
+                    )
+                },
+            );
 
-impl Tile {
-    /// Create a tile from an ASCII character.
-    pub fn from_ascii(ascii: char) -> Self {
-        match ascii {
-            ' ' => Tile::Empty,
-            '*' => Tile::Any,
-            'o' => Tile::Wheel,
-            '-' => Tile::HorizontalBeam,
-            '|' => Tile::VerticalBeam,
-            '/' => Tile::DiagonalBeam1,
-            '\\' => Tile::DiagonalBeam2,
-            '+' => Tile::Cross,
-            '.' => Tile::Wall,
-            _ => panic!("ascii character not supported"),
+        if total_mass == 0.0 {
+            (self.width as f32 / 2.0, self.height as f32 / 2.0)
+        } else {
+            (x_sum / total_mass, y_sum / total_mass)
         }
     }
 
-    /// Convert it to a single ASCII character.
-    pub fn to_ascii(self) -> char {
-        match self {
-            Tile::Empty => ' ',
-            Tile::Any => '*',
-            Tile::Wheel => 'o',
-            Tile::HorizontalBeam => '-',
-            Tile::VerticalBeam => '|',
-            Tile::DiagonalBeam1 => '/',
-            Tile::DiagonalBeam2 => '\\',
-            Tile::Cross => '+',
-            Tile::Wall => '.',
-            Tile::Edge => panic!("this tile should've been removed from the output"),
+    /// All wheels as circles, in tiles.
+    ///
+    /// Wheel tiles next to each other are merged into a single bigger wheel, centered on the
+    /// group and with a diameter spanning it's longest side. Wheels touching diagonally stay
+    /// separate. The wheels are sorted by their top left tile in row-major order.
+    pub fn wheels(&self) -> Vec<WheelInfo> {
+        let mut visited = vec![false; self.tiles.len()];
+        let mut wheels = vec![];
+
+        for start in 0..self.tiles.len() {
+            if visited[start] || self.tiles[start] != Tile::Wheel {
+                continue;
+            }
+
+            // Flood fill the group, keeping track of it's bounding box
+            visited[start] = true;
+            let (x, y) = (start as u32 % self.width, start as u32 / self.width);
+            let (mut min_x, mut min_y, mut max_x, mut max_y) = (x, y, x, y);
+            let mut stack = vec![(x, y)];
+            while let Some((x, y)) = stack.pop() {
+                min_x = min_x.min(x);
+                min_y = min_y.min(y);
+                max_x = max_x.max(x);
+                max_y = max_y.max(y);
+
+                let neighbors = [
+                    (x.wrapping_sub(1), y),
+                    (x + 1, y),
+                    (x, y.wrapping_sub(1)),
+                    (x, y + 1),
+                ];
+                for &(x, y) in neighbors.iter() {
+                    match self.index(x, y) {
+                        Some(index) if !visited[index] && self.tiles[index] == Tile::Wheel => {
+                            visited[index] = true;
+
+                            stack.push((x, y));
+                        }
+                        _ => (),
+                    }
+                }
+            }
+
+            let (width, height) = ((max_x - min_x + 1) as f32, (max_y - min_y + 1) as f32);
+            wheels.push(WheelInfo {
+                center: (min_x as f32 + width / 2.0, min_y as f32 + height / 2.0),
+                radius: width.max(height) / 2.0,
+            });
         }
-    }
 
-    /// Whether a tile is either empty or an edge.
-    fn is_empty(self) -> bool {
-        self == Tile::Empty || self == Tile::Edge
+        wheels
     }
 
-    /// Find the proper any tile depending on it's neighbors.
-    pub fn fill_any(index: usize, tiles: &Vec<Tile>, width: usize) -> Tile {
-        // We never have to check for boundaries since they are always edge tiles
-        let up = !tiles[index - width].is_empty();
-        let down = !tiles[index + width].is_empty();
-        let left = !tiles[index - 1].is_empty();
-        let right = !tiles[index + 1].is_empty();
-
-        if up && down && left && right {
-            // Check for corners
-            let up_left = !tiles[index - width - 1].is_empty();
-            let up_right = !tiles[index - width + 1].is_empty();
-            let down_left = !tiles[index + width - 1].is_empty();
-            let down_right = !tiles[index + width + 1].is_empty();
-
-            if !up_left || !up_right || !down_left || !down_right {
-                Tile::Cross
-            } else {
-                Tile::Wall
+    /// Add rivets to the crosses where beams come together.
+    ///
+    /// Every cross that's connected on at least three sides has an even chance to become a
+    /// `Joint`. The result only depends on the random number generator, so decorating the same
+    /// engine with the same seed always gives the same result.
+    pub fn decorate<R>(&mut self, rng: &mut R)
+    where
+        R: Rng,
+    {
+        let joints = (0..self.tiles.len())
+            .filter(|index| {
+                let (x, y) = (*index as u32 % self.width, *index as u32 / self.width);
+                let sides = [(0, -1), (1, 0), (0, 1), (-1, 0)]
+                    .iter()
+                    .filter(|(dx, dy)| self.connects(x, y, *dx, *dy))
+                    .count();
+
+                self.tiles[*index] == Tile::Cross && sides >= 3
+            })
+            .collect::<Vec<_>>();
+
+        for index in joints {
+            if rng.gen_bool(0.5) {
+                self.tiles[index] = Tile::Joint;
             }
-        } else if up && down {
-            Tile::VerticalBeam
-        } else if left && right {
-            Tile::HorizontalBeam
-        } else {
-            Tile::Cross
         }
     }
-}
 
-impl FromStr for Tile {
-    type Err = Error;
+    /// Turn beams that are connected from the side into crosses.
+    ///
+    /// A horizontal beam with a vertical connection above or below it, or a vertical beam with a
+    /// horizontal connection left or right of it, is where beams meet so it becomes a `Cross`.
+    /// This covers intersections, T-junctions & corners the collapse left as plain beams.
+    pub fn normalize_joints(&mut self) {
+        let crosses = (0..self.tiles.len())
+            .filter(|index| {
+                let (x, y) = (*index as u32 % self.width, *index as u32 / self.width);
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        // First attempt to convert it from a number
-        Ok(match s.parse::<u8>() {
-            Ok(num) => match FromPrimitive::from_u8(num) {
-                Some(tile) => tile,
-                None => {
-                    return Err(anyhow!("no tile with this number can be mapped"));
+                match self.tiles[*index] {
+                    Tile::HorizontalBeam => self.connects(x, y, 0, -1) || self.connects(x, y, 0, 1),
+                    Tile::VerticalBeam => self.connects(x, y, -1, 0) || self.connects(x, y, 1, 0),
+                    _ => false,
                 }
-            },
-            Err(err) => {
-                // TODO allow usage of letters and names and handle them here
-                return Err(anyhow!("{}", err));
-            }
-        })
+            })
+            .collect::<Vec<_>>();
+
+        for index in crosses {
+            self.tiles[index] = Tile::Cross;
+        }
     }
-}
 
-/// The siege engine generator.
-pub struct Generator {
-    pub grid: Grid<Tile>,
-    pub overlapping_patterns: OverlappingPatterns<Tile>,
-}
+    /// Whether the tile next to the position in the direction connects to it.
+    fn connects(&self, x: u32, y: u32, dx: i32, dy: i32) -> bool {
+        let neighbor = self
+            .index((x as i32 + dx) as u32, (y as i32 + dy) as u32)
+            .map(|index| self.tiles[index]);
 
-impl Generator {
-    /// Use a template from an array of numbers.
-    pub fn from_array(width: u32, height: u32, map: &[Tile]) -> Result<Self> {
-        if map.len() != (width * height) as usize {
-            return Err(anyhow!("array doesn't match width & height"));
+        match neighbor {
+            Some(Tile::Cross) | Some(Tile::Joint) | Some(Tile::Wheel) => true,
+            Some(Tile::HorizontalBeam) => dy == 0,
+            Some(Tile::VerticalBeam) => dx == 0,
+            _ => false,
         }
+    }
 
-        // Add 2 to the dimensions to pad all sides with empty tiles later
-        let size = Size::new(width + 2, height + 2);
+    /// Label every tile with the structure it's part of.
+    ///
+    /// Returns the amount of separate structures and a label for every tile, `None` for empty
+    /// tiles. Tiles next to each other are connected, tiles touching diagonally are only
+    /// connected when one of them is a diagonal beam going in that direction.
+    pub fn components(&self) -> (usize, Vec<Option<usize>>) {
+        let mut labels = vec![None; self.tiles.len()];
+        let mut count = 0;
 
-        // Convert the array to a 2d grid
-        let grid = Grid::new_fn(size, |coord| {
-            if coord_is_edge(coord, size.width() as i32, size.height() as i32) {
-                // Pad the with empty tiles
-                Tile::Edge
-            } else {
-                map[((coord.y - 1) * (width as i32) + (coord.x - 1)) as usize]
+        for start in 0..self.tiles.len() {
+            if labels[start].is_some() || self.tiles[start].is_empty() {
+                continue;
             }
-        });
 
-        let overlapping_patterns = OverlappingPatterns::new_original_orientation(
-            grid.clone(),
-            NonZeroU32::new(PATTERN_SIZE as u32).unwrap(),
-        );
+            // Flood fill the whole structure
+            labels[start] = Some(count);
+            let mut stack = vec![start];
+            while let Some(index) = stack.pop() {
+                for neighbor in self.connected_neighbors(index) {
+                    if labels[neighbor].is_none() {
+                        labels[neighbor] = Some(count);
+                        stack.push(neighbor);
+                    }
+                }
+            }
 
-        Ok(Self {
-            grid,
-            overlapping_patterns,
+            count += 1;
+        }
+
+        (count, labels)
+    }
+
+    /// The tiles connected to the tile at the index, following the rules of `components`.
+    pub(crate) fn connected_neighbors(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        let (x, y) = (index as u32 % self.width, index as u32 / self.width);
+
+        NEIGHBOR_OFFSETS.iter().filter_map(move |&(dx, dy)| {
+            let neighbor = self.index((x as i32 + dx) as u32, (y as i32 + dy) as u32)?;
+            if self.tiles[neighbor].is_empty() {
+                return None;
+            }
+
+            let connected = if dx == 0 || dy == 0 {
+                true
+            } else {
+                // Up right & down left for '/', up left & down right for '\'
+                let diagonal = if dx == dy {
+                    Tile::DiagonalBeam2
+                } else {
+                    Tile::DiagonalBeam1
+                };
+
+                self.tiles[index] == diagonal || self.tiles[neighbor] == diagonal
+            };
+
+            if connected {
+                Some(neighbor)
+            } else {
+                None
+            }
         })
     }
 
-    /// Use an ASCII art template.
-    ///
-    /// The symbols used are described in [`Tile`].
-    ///
-    /// [`Tile`]: enum.Tile.html
-    pub fn from_ascii<S>(ascii: S) -> Result<Self>
-    where
-        S: AsRef<str>,
-    {
-        let mut height = 0;
-        let mut tiles = vec![];
+    /// Fraction of the tiles that aren't empty.
+    pub fn density(&self) -> f32 {
+        if self.tiles.is_empty() {
+            return 0.0;
+        }
+
+        let filled = self.tiles.iter().filter(|tile| !tile.is_empty()).count();
 
-        // Iterate over all the lines in the text
-        for line in ascii.as_ref().lines() {
-            // Skip empty lines
-            if line.is_empty() {
+        filled as f32 / self.tiles.len() as f32
+    }
+
+    /// Fraction of the engine covered by the bounding box of all tiles that aren't empty.
+    pub fn fill_ratio(&self) -> f32 {
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (u32::MAX, u32::MAX, 0, 0);
+        for (index, tile) in self.tiles.iter().enumerate() {
+            if tile.is_empty() {
                 continue;
             }
 
-            // Map the text characters to tiles in a vector
-            let line_tiles = line
-                .chars()
-                .map(|ch| Tile::from_ascii(ch))
-                .collect::<Vec<_>>();
-            tiles.push(line_tiles);
+            let (x, y) = (index as u32 % self.width, index as u32 / self.width);
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
 
-            height += 1;
+        if min_x > max_x {
+            // Nothing is filled
+            return 0.0;
         }
 
-        // Get the biggest line width
-        let width = tiles
-            .iter()
-            .max_by_key(|line_tiles| line_tiles.len())
-            .ok_or_else(|| anyhow!("could not find maximum in array"))?
-            .len();
+        ((max_x - min_x + 1) * (max_y - min_y + 1)) as f32 / self.tiles.len() as f32
+    }
 
-        let tiles = tiles
-            .into_iter()
-            .map(|mut line_tiles| {
-                if width > line_tiles.len() {
-                    // Fill the tiles with smaller widths with empty tiles
-                    line_tiles.resize_with(width, || Tile::Empty);
-                }
+    /// The width divided by the height, bigger than 1 for wide engines.
+    ///
+    /// An engine without any rows has no shape to speak of, so this is 0 then instead of infinite.
+    pub fn aspect_ratio(&self) -> f32 {
+        if self.height == 0 {
+            return 0.0;
+        }
+
+        self.width as f32 / self.height as f32
+    }
+
+    /// Fraction of the tiles that are the same as their mirrored counterpart over the axis.
+    ///
+    /// Mirroring flips the direction of diagonal beams, so a `/` on the left matches a `\` on the
+    /// right. A perfectly symmetric engine scores `1.0`.
+    pub fn symmetry_score(&self, axis: Axis) -> f32 {
+        if self.tiles.is_empty() {
+            return 1.0;
+        }
+
+        let (width, height) = (self.width as usize, self.height as usize);
+        let matching = self
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(index, tile)| {
+                let (x, y) = (index % width, index / width);
+                let mirrored_index = match axis {
+                    Axis::Vertical => y * width + width - 1 - x,
+                    Axis::Horizontal => (height - 1 - y) * width + x,
+                };
 
-                line_tiles
+                self.tiles[mirrored_index].mirrored() == **tile
             })
-            .flatten()
-            .collect::<Vec<_>>();
+            .count();
 
-        Self::from_array(width as u32, height, &tiles[..])
+        matching as f32 / self.tiles.len() as f32
     }
 
-    /// Use a template from a path pointing to an ASCII file.
+    /// How visually interesting the engine is, useful for ranking generated engines.
     ///
-    /// The symbols used are described in [`Tile`].
+    /// The score is calculated as:
     ///
-    /// [`Tile`]: enum.Tile.html
-    pub fn from_ascii_file<P>(path: P) -> Result<Self>
-    where
-        P: AsRef<Path>,
-    {
-        // Read the file
-        let mut file_contents = String::new();
-        File::open(path)?.read_to_string(&mut file_contents)?;
+    /// ```text
+    /// tile_types * distinct non-empty tile types
+    ///     + direction_changes * beam direction changes
+    ///     + wheels * wheel count
+    /// ```
+    ///
+    /// A beam direction change is a pair of horizontally or vertically neighboring beam tiles,
+    /// including crosses & joints, that are not the same tile. A plain block of walls scores
+    /// `weights.tile_types`.
+    pub fn complexity_score(&self, weights: ComplexityWeights) -> f32 {
+        let tile_types = self
+            .tiles
+            .iter()
+            .filter(|tile| !tile.is_empty())
+            .collect::<HashSet<_>>()
+            .len();
 
-        // Parse it
-        Self::from_ascii(file_contents)
+        let is_beam = |tile: Tile| {
+            matches!(
+                tile,
+                Tile::HorizontalBeam
+                    | Tile::VerticalBeam
+                    | Tile::DiagonalBeam1
+                    | Tile::DiagonalBeam2
+                    | Tile::Cross
+                    | Tile::Joint
+            )
+        };
+        let width = self.width as usize;
+        let direction_changes = self
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, tile)| is_beam(**tile))
+            .map(|(index, tile)| {
+                // Only look right & down so every pair is counted once
+                let right = if (index + 1) % width != 0 {
+                    self.tiles.get(index + 1)
+                } else {
+                    None
+                };
+                let down = self.tiles.get(index + width);
+
+                [right, down]
+                    .iter()
+                    .filter(|neighbor| {
+                        matches!(neighbor, Some(neighbor) if is_beam(**neighbor) && *neighbor != tile)
+                    })
+                    .count()
+            })
+            .sum::<usize>();
+
+        let wheels = self
+            .tiles
+            .iter()
+            .filter(|tile| **tile == Tile::Wheel)
+            .count();
+
+        weights.tile_types * tile_types as f32
+            + weights.direction_changes * direction_changes as f32
+            + weights.wheels * wheels as f32
     }
 
-    /// Generate a 2D grid with the output dimensions passed of a randomly generated siege engine.
-    pub fn generate_skeleton<R>(
-        &self,
-        output_width: u32,
-        output_height: u32,
-        retry_times: usize,
-        rng: &mut R,
-    ) -> Option<Engine>
-    where
-        R: Rng,
-    {
-        let width_with_edge = output_width + 2;
-        let height_with_edge = output_height + 2;
+    /// The horizontal span of the lowest row containing any structure, including wheels.
+    ///
+    /// Returns the minimum and maximum x of the non-empty tiles in that row, or `None` when the
+    /// engine is completely empty.
+    pub fn footprint(&self) -> Option<(u32, u32)> {
+        let row = self
+            .tiles
+            .chunks(self.width as usize)
+            .rev()
+            .find(|row| row.iter().any(|tile| !tile.is_empty()))?;
 
-        // Construct the WFC runner
-        let global_stats = self.overlapping_patterns.global_stats();
-        let run = RunOwn::new_forbid(
-            Size::new(width_with_edge, height_with_edge),
-            &global_stats,
-            self.force_border_forbid(),
-            rng,
-        );
+        let min = row.iter().position(|tile| !tile.is_empty())?;
+        let max = row.iter().rposition(|tile| !tile.is_empty())?;
 
-        // Attempt to collapse with a set amount of retries
-        let wave = run.collapse_retrying(NumTimes(retry_times), rng).ok()?;
-        let grid = wave.grid();
+        Some((min as u32, max as u32))
+    }
 
-        // Map the result to indices of the original vector
-        let tiles = grid
-            .iter()
-            .map(|cell| {
-                // Get the original index
-                let pattern_id = cell.chosen_pattern_id().unwrap();
-                self.overlapping_patterns
-                    .pattern_top_left_value(pattern_id)
-                    .clone()
+    /// Find all wheels that aren't attached to the frame.
+    ///
+    /// A wheel is attached when any of it's eight neighbors, including the diagonal ones, is a
+    /// structural tile. Other wheels don't count as structure.
+    pub fn unattached_wheels(&self) -> Vec<Coord> {
+        let grid = self.to_grid();
+
+        grid.enumerate()
+            .filter(|(_, tile)| **tile == Tile::Wheel)
+            .map(|(coord, _)| coord)
+            .filter(|coord| {
+                !NEIGHBOR_OFFSETS.iter().any(|(x, y)| {
+                    matches!(grid.get(*coord + Coord::new(*x, *y)),
+                        Some(tile) if !tile.is_empty() && *tile != Tile::Wheel)
+                })
             })
-            .collect::<Vec<_>>();
+            .collect()
+    }
+
+    /// The outline of the structure as a clockwise loop of corner points, in tiles.
+    ///
+    /// The outline follows the outer border of the non-empty tiles connected to the first
+    /// non-empty tile in row-major order, tiles only touching diagonally are also seen as
+    /// connected. Holes in the structure are ignored. Returns an empty vector for an empty engine.
+    pub fn outline(&self) -> Vec<(f32, f32)> {
+        let is_filled = |x: i32, y: i32| {
+            x >= 0
+                && y >= 0
+                && matches!(self.index(x as u32, y as u32), Some(index) if !self.tiles[index].is_empty())
+        };
+
+        let start = match self.tiles.iter().position(|tile| !tile.is_empty()) {
+            Some(index) => (
+                (index as u32 % self.width) as i32,
+                (index as u32 / self.width) as i32,
+            ),
+            None => return vec![],
+        };
+
+        // All sides of the filled tiles bordering empty space as directed edges between corners,
+        // going clockwise around the filled tiles
+        let mut edges: HashMap<(i32, i32), Vec<(i32, i32)>> = HashMap::new();
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                if !is_filled(x, y) {
+                    continue;
+                }
+
+                let sides = [
+                    ((0, -1), (x, y), (x + 1, y)),
+                    ((1, 0), (x + 1, y), (x + 1, y + 1)),
+                    ((0, 1), (x + 1, y + 1), (x, y + 1)),
+                    ((-1, 0), (x, y + 1), (x, y)),
+                ];
+                for &((dx, dy), from, to) in sides.iter() {
+                    if !is_filled(x + dx, y + dy) {
+                        edges.entry(from).or_default().push(to);
+                    }
+                }
+            }
+        }
+
+        // Walk along the edges starting at the top left corner of the first tile
+        let mut points = vec![start];
+        let mut direction = (1, 0);
+        let mut corner = start;
+        loop {
+            let outgoing = &edges[&corner];
+
+            // Prefer turning left, so tiles touching diagonally are followed
+            let (dx, dy) = direction;
+            let next = [(dy, -dx), (dx, dy), (-dy, dx)]
+                .iter()
+                .map(|(dx, dy)| (corner.0 + dx, corner.1 + dy))
+                .find(|next| outgoing.contains(next))
+                .expect("outline is not closed");
+
+            direction = (next.0 - corner.0, next.1 - corner.1);
+            corner = next;
+            if corner == start {
+                break;
+            }
+
+            points.push(corner);
+        }
+
+        // Only keep the points where the direction changes
+        let count = points.len();
+        (0..count)
+            .filter(|index| {
+                let (prev, point, next) = (
+                    points[(index + count - 1) % count],
+                    points[*index],
+                    points[(index + 1) % count],
+                );
+
+                (point.0 - prev.0, point.1 - prev.1) != (next.0 - point.0, next.1 - point.1)
+            })
+            .map(|index| (points[index].0 as f32, points[index].1 as f32))
+            .collect()
+    }
+
+    /// Which neighbors of a tile are structural, `None` when the position is outside of the
+    /// engine.
+    ///
+    /// Neighbors outside of the engine are never structural.
+    pub fn tile_context(&self, x: u32, y: u32) -> Option<TileContext> {
+        self.index(x, y)?;
 
-        let tiles = tiles
+        Some(TileContext::from_fn(|dx, dy| {
+            let (x, y) = (x as i64 + dx as i64, y as i64 + dy as i64);
+
+            x >= 0
+                && y >= 0
+                && matches!(self.index(x as u32, y as u32), Some(index) if !self.tiles[index].is_empty())
+        }))
+    }
+
+    /// Find all pairs of a tile that are too close to each other.
+    ///
+    /// There must be at least `min_spacing` other tiles in between, counting diagonally as a
+    /// single step, so a spacing of `1` means the tiles can't be neighbors. Every pair is only
+    /// reported once, with the first tile in row-major order first.
+    pub fn spacing_violations(&self, tile: Tile, min_spacing: u32) -> Vec<(Coord, Coord)> {
+        let positions = self.positions_of(tile);
+
+        positions
             .iter()
-            // Fill in cells with an any symbol
             .enumerate()
-            .map(|(index, tile)| {
-                if *tile == Tile::Any {
-                    Tile::fill_any(index, &tiles, width_with_edge as usize)
-                } else {
-                    *tile
-                }
+            .flat_map(|(index, first)| {
+                positions[index + 1..]
+                    .iter()
+                    .filter(move |second| too_close(*first, **second, min_spacing))
+                    .map(move |second| (*first, *second))
             })
-            // Remove all edge tiles
-            .filter(|tile| *tile != Tile::Edge)
-            .collect();
+            .collect()
+    }
 
-        Some(Engine {
-            tiles,
-            width: output_width,
-            height: output_height,
-        })
+    /// Remove tiles that are too close to another one of the same tile.
+    ///
+    /// The tiles are checked in row-major order and removed when they are too close to a tile that
+    /// was kept, see [`spacing_violations`] for how the spacing is measured. Returns the amount of
+    /// tiles that are replaced with `Tile::Empty`.
+    ///
+    /// [`spacing_violations`]: struct.Engine.html#method.spacing_violations
+    pub fn enforce_tile_spacing(&mut self, tile: Tile, min_spacing: u32) -> usize {
+        let mut kept: Vec<Coord> = vec![];
+        let mut removed = 0;
+
+        for coord in self.positions_of(tile) {
+            if kept
+                .iter()
+                .any(|other| too_close(*other, coord, min_spacing))
+            {
+                let index = self.index(coord.x as u32, coord.y as u32).unwrap();
+                self.tiles[index] = Tile::Empty;
+
+                removed += 1;
+            } else {
+                kept.push(coord);
+            }
+        }
+
+        removed
     }
 
-    /// Create the forbid pattern.
-    fn force_border_forbid(&self) -> ForceBorderForbid {
-        let size = self.grid.size();
-        let width = size.width() as i32;
-        let height = size.height() as i32;
+    /// Replace the region of equal tiles around a position with another tile, like a bucket fill.
+    ///
+    /// Only tiles next to each other are part of the same region, tiles touching diagonally
+    /// aren't. Returns the amount of tiles that changed, which is zero when the position is
+    /// outside of the engine, the tile is already the new tile or the new tile is `Tile::Edge`.
+    pub fn flood_replace(&mut self, x: u32, y: u32, new: Tile) -> usize {
+        let start = match self.index(x, y) {
+            Some(start) => start,
+            None => return 0,
+        };
+        let old = self.tiles[start];
+        // Edges are only used for padding during generation
+        if new == Tile::Edge || old == new {
+            return 0;
+        }
 
-        let overlapping_grid = self.overlapping_patterns.id_grid_original_orientation();
+        self.tiles[start] = new;
+        let mut replaced = 1;
+        let mut stack = vec![(x, y)];
+        while let Some((x, y)) = stack.pop() {
+            let neighbors = [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ];
 
-        // Always let the corner be empty tiles
-        let top_left = *overlapping_grid.get_checked(Coord::new(0, 0));
-        let bot_left = *overlapping_grid.get_checked(Coord::new(0, height - 1));
-        let top_right = *overlapping_grid.get_checked(Coord::new(width - 1, 0));
-        let bot_right = *overlapping_grid.get_checked(Coord::new(width - 1, height - 1));
+            for &(x, y) in neighbors.iter() {
+                match self.index(x, y) {
+                    Some(index) if self.tiles[index] == old => {
+                        self.tiles[index] = new;
+                        replaced += 1;
 
-        // Special one for the middle of the top so it's height matches
-        let top_mid = *overlapping_grid.get_checked(Coord::new(width / 2, 0));
-
-        // Get all the patterns containing empty tiles
-        let mut pattern_ids = Vec::with_capacity(width as usize * 2 + height as usize * 2);
-        for x in 0..width {
-            pattern_ids.push(overlapping_grid.get_checked(Coord::new(x, 0)).clone());
-            pattern_ids.push(
-                overlapping_grid
-                    .get_checked(Coord::new(x, height - 1))
-                    .clone(),
-            );
+                        stack.push((x, y));
+                    }
+                    _ => (),
+                }
+            }
         }
-        for y in 0..height {
-            pattern_ids.push(overlapping_grid.get_checked(Coord::new(0, y)).clone());
-            pattern_ids.push(
-                overlapping_grid
-                    .get_checked(Coord::new(width - 1, y))
-                    .clone(),
-            );
+
+        replaced
+    }
+
+    /// The positions of all tiles of a type in row-major order.
+    fn positions_of(&self, tile: Tile) -> Vec<Coord> {
+        self.tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, other)| **other == tile)
+            .map(|(index, _)| {
+                Coord::new(
+                    (index as u32 % self.width) as i32,
+                    (index as u32 / self.width) as i32,
+                )
+            })
+            .collect()
+    }
+
+    /// Replace all `Tile::Any` tiles following an edge detection heuristic.
+    ///
+    /// The heuristic is described in [`DefaultAnyResolver`], tiles outside of the engine are
+    /// treated as empty. Use [`resolve_any_with`] for a different strategy.
+    ///
+    /// [`DefaultAnyResolver`]: struct.DefaultAnyResolver.html
+    /// [`resolve_any_with`]: struct.Engine.html#method.resolve_any_with
+    pub fn resolve_any(&mut self) {
+        self.resolve_any_with(&DefaultAnyResolver);
+    }
+
+    /// Surround the engine with empty tiles.
+    ///
+    /// The amounts are the numbers of empty columns & rows added on each side.
+    pub fn pad(&self, left: u32, right: u32, top: u32, bottom: u32) -> Engine {
+        let width = left + self.width + right;
+        let height = top + self.height + bottom;
+
+        let mut tiles = vec![Tile::Empty; width as usize * height as usize];
+        // An engine without width has no rows to copy, which `chunks` can't handle
+        let rows = self.tiles.chunks(self.width.max(1) as usize);
+        for (y, row) in rows.enumerate() {
+            let start = (y + top as usize) * width as usize + left as usize;
+            tiles[start..start + row.len()].copy_from_slice(row);
         }
 
-        ForceBorderForbid {
-            pattern_ids,
-            top_left,
-            bot_left,
-            top_right,
-            bot_right,
-            top_mid,
+        Engine {
+            width,
+            height,
+            tiles,
         }
     }
-}
 
-impl Default for Generator {
-    fn default() -> Self {
-        Self::from_ascii(include_str!("default.ascii")).unwrap()
+    /// Copy a rectangle of tiles into a new engine.
+    ///
+    /// Fails when the rectangle is empty or doesn't fit inside the engine.
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Result<Engine> {
+        if width == 0 || height == 0 {
+            return Err(anyhow!(
+                "cropped engine must have a size of at least one tile"
+            ));
+        }
+        let fits = |start: u32, size: u32, max: u32| matches!(start.checked_add(size), Some(end) if end <= max);
+        if !fits(x, width, self.width) || !fits(y, height, self.height) {
+            return Err(anyhow!(
+                "crop rectangle ({}, {}, {}, {}) is out of bounds for an engine of {}x{}",
+                x,
+                y,
+                width,
+                height,
+                self.width,
+                self.height
+            ));
+        }
+
+        let tiles = self
+            .tiles
+            .chunks(self.width as usize)
+            .skip(y as usize)
+            .take(height as usize)
+            .flat_map(|row| row[x as usize..(x + width) as usize].iter().copied())
+            .collect();
+
+        Ok(Engine {
+            width,
+            height,
+            tiles,
+        })
     }
-}
 
-/// Ensures that the the edges will always be filled with empty tiles.
-#[derive(Clone)]
-struct ForceBorderForbid {
-    pattern_ids: Vec<PatternId>,
-    top_left: PatternId,
-    bot_left: PatternId,
-    top_right: PatternId,
-    bot_right: PatternId,
-    top_mid: PatternId,
+    /// Build a graph of how the beams, crosses & wheels are connected.
+    ///
+    /// Use [`EngineGraph::to_dot`] to render it with Graphviz.
+    ///
+    /// [`EngineGraph::to_dot`]: struct.EngineGraph.html#method.to_dot
+    pub fn to_graph(&self) -> EngineGraph {
+        EngineGraph::from_engine(self)
+    }
+
+    /// The array tiles as a one dimensional vector.
+    pub fn tiles(&self) -> &Vec<Tile> {
+        &self.tiles
+    }
+
+    /// Amount of tiles in a row.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Amount of tiles in a column.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
 }
 
-impl ForbidPattern for ForceBorderForbid {
-    fn forbid<W: Wrap, R: Rng>(&mut self, fi: &mut ForbidInterface<W>, rng: &mut R) {
-        let output_size = fi.wave_size();
-        let width = output_size.width() as i32;
-        let height = output_size.height() as i32;
+impl TryFrom<Vec<Vec<Tile>>> for Engine {
+    type Error = Error;
 
-        fi.forbid_all_patterns_except(Coord::new(0, 0), self.top_left, rng)
-            .unwrap();
-        fi.forbid_all_patterns_except(Coord::new(0, height - 1), self.bot_left, rng)
-            .unwrap();
-        fi.forbid_all_patterns_except(Coord::new(width - 1, 0), self.top_right, rng)
-            .unwrap();
-        fi.forbid_all_patterns_except(Coord::new(width - 1, height - 1), self.bot_right, rng)
-            .unwrap();
-        fi.forbid_all_patterns_except(Coord::new(width / 2, 0), self.top_mid, rng)
-            .unwrap();
+    /// Create an engine from a vector of rows, the inverse of [`Engine::to_rows`].
+    ///
+    /// All rows must have the same length and can't contain any `Tile::Edge` tiles.
+    ///
+    /// [`Engine::to_rows`]: struct.Engine.html#method.to_rows
+    fn try_from(rows: Vec<Vec<Tile>>) -> Result<Self, Self::Error> {
+        let width = rows.first().map_or(0, |row| row.len());
+        if let Some(y) = rows.iter().position(|row| row.len() != width) {
+            return Err(anyhow!(
+                "row {} has {} tiles but the first row has {}",
+                y,
+                rows[y].len(),
+                width
+            ));
+        }
 
-        for x in 0..width {
-            for y in 0..height {
-                let coord = Coord::new(x, y);
-                if !coord_is_edge(coord, width, height) {
-                    // The inside is never allowed to be an edge
-                    self.pattern_ids
-                        .iter()
-                        .for_each(|pattern_id| fi.forbid_pattern(coord, *pattern_id, rng).unwrap());
-                }
-            }
+        let height = rows.len();
+        let tiles = rows.concat();
+        if tiles.contains(&Tile::Edge) {
+            return Err(anyhow!("rows contain an edge tile, which is reserved"));
         }
+
+        Ok(Self {
+            width: width as u32,
+            height: height as u32,
+            tiles,
+        })
     }
 }
 
-fn coord_is_edge(coord: Coord, width: i32, height: i32) -> bool {
-    coord.x == 0 || coord.x == width - 1 || coord.y == 0 || coord.y == height - 1
+/// Grid section of the siege engine.
+///
+/// This enum can be mapped to an ASCII character.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, FromPrimitive)]
+pub enum Tile {
+    /// `' '` ASCII: empty space.
+    Empty,
+    /// `'*'` ASCII: any type, will be automatically filled following an edge detection heuristic.
+    Any,
+    /// `'o'` ASCII: a single wheel.
+    Wheel,
+    /// `'-'` ASCII: a beam connecting the tile left and the tile right.
+    HorizontalBeam,
+    /// `'|'` ASCII: a beam connecting the tile above and the tile below.
+    VerticalBeam,
+    /// `'/'` ASCII: a beam connecting connecting left bottom to right top.
+    DiagonalBeam1,
+    /// `'\'` ASCII: a beam connecting connecting left top to right bottom.
+    DiagonalBeam2,
+    /// `'+'` ASCII: a cross section connecting beams.
+    Cross,
+    /// `'.'` ASCII: a wall tile in between beams.
+    Wall,
+    /// `'#'` ASCII: a cross section with a rivet.
+    ///
+    /// Generating never creates joints unless the template contains them, [`Engine::decorate`]
+    /// adds them to a finished engine instead.
+    ///
+    /// [`Engine::decorate`]: struct.Engine.html#method.decorate
+    Joint,
+
+    /// `'~'` ASCII: edge tile, used internally.
+    ///
+    /// Only visible in the output of [`Generator::generate_skeleton_with_edges`].
+    ///
+    /// [`Generator::generate_skeleton_with_edges`]: struct.Generator.html#method.generate_skeleton_with_edges
+    #[doc(hidden)]
+    Edge = 255,
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::*;
-    use anyhow::Result;
+impl Tile {
+    /// Create a tile from an ASCII character.
+    ///
+    /// The Unicode box-drawing characters returned by [`to_unicode`] are also accepted.
+    ///
+    /// [`to_unicode`]: enum.Tile.html#method.to_unicode
+    ///
+    /// # Panics
+    ///
+    /// When the character doesn't map to any tile, use [`try_from_ascii`] to handle that case.
+    ///
+    /// [`try_from_ascii`]: enum.Tile.html#method.try_from_ascii
+    pub fn from_ascii(ascii: char) -> Self {
+        Self::try_from_ascii(ascii).unwrap_or_else(|err| panic!("{}", err))
+    }
 
-    #[test]
-    fn default_test() -> Result<()> {
-        let result = Generator::default().generate_skeleton(10, 10, 100, &mut rand::thread_rng());
-        assert!(result.is_some());
+    /// Create a tile from an ASCII character, failing when the character isn't supported.
+    ///
+    /// The Unicode box-drawing characters returned by [`to_unicode`] are also accepted.
+    ///
+    /// [`to_unicode`]: enum.Tile.html#method.to_unicode
+    pub fn try_from_ascii(ascii: char) -> Result<Self> {
+        Ok(match ascii {
+            ' ' => Tile::Empty,
+            '*' => Tile::Any,
+            'o' => Tile::Wheel,
+            '-' | '─' => Tile::HorizontalBeam,
+            '|' | '│' => Tile::VerticalBeam,
+            '/' | '╱' => Tile::DiagonalBeam1,
+            '\\' | '╲' => Tile::DiagonalBeam2,
+            '+' | '┼' => Tile::Cross,
+            '.' => Tile::Wall,
+            '#' | '╋' => Tile::Joint,
+            _ => return Err(anyhow!("ascii character {:?} not supported", ascii)),
+        })
+    }
 
-        Ok(())
+    /// Convert it to a single ASCII character.
+    pub fn to_ascii(self) -> char {
+        match self {
+            Tile::Empty => ' ',
+            Tile::Any => '*',
+            Tile::Wheel => 'o',
+            Tile::HorizontalBeam => '-',
+            Tile::VerticalBeam => '|',
+            Tile::DiagonalBeam1 => '/',
+            Tile::DiagonalBeam2 => '\\',
+            Tile::Cross => '+',
+            Tile::Wall => '.',
+            Tile::Joint => '#',
+            Tile::Edge => '~',
+        }
+    }
+
+    /// Convert it to a single character, using Unicode box-drawing characters for the beams.
+    pub fn to_unicode(self) -> char {
+        match self {
+            Tile::HorizontalBeam => '─',
+            Tile::VerticalBeam => '│',
+            Tile::DiagonalBeam1 => '╱',
+            Tile::DiagonalBeam2 => '╲',
+            Tile::Cross => '┼',
+            Tile::Joint => '╋',
+            _ => self.to_ascii(),
+        }
+    }
+
+    /// The `'/'` diagonal beam connecting left bottom to right top.
+    pub fn diagonal_slash() -> Self {
+        Tile::DiagonalBeam1
+    }
+
+    /// The `'\'` diagonal beam connecting left top to right bottom.
+    pub fn diagonal_backslash() -> Self {
+        Tile::DiagonalBeam2
+    }
+
+    /// The direction of a diagonal beam, `None` for all other tiles.
+    pub fn diagonal_direction(self) -> Option<DiagDir> {
+        match self {
+            Tile::DiagonalBeam1 => Some(DiagDir::Forward),
+            Tile::DiagonalBeam2 => Some(DiagDir::Back),
+            _ => None,
+        }
+    }
+
+    /// The numeric value of the tile, which can be converted back with `FromPrimitive::from_u8`.
+    ///
+    /// These are the numbers used by [`from_array`] templates & the `FromStr` implementation. The
+    /// internal edge tile is `255`.
+    ///
+    /// [`from_array`]: struct.Generator.html#method.from_array
+    pub fn as_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// Whether a tile is either empty or an edge.
+    fn is_empty(self) -> bool {
+        self == Tile::Empty || self == Tile::Edge
+    }
+
+    /// The tile as it looks when mirrored, which only changes the direction of diagonal beams.
+    fn mirrored(self) -> Self {
+        match self {
+            Tile::DiagonalBeam1 => Tile::DiagonalBeam2,
+            Tile::DiagonalBeam2 => Tile::DiagonalBeam1,
+            tile => tile,
+        }
+    }
+
+    /// Find the proper any tile depending on it's neighbors, see [`DefaultAnyResolver`].
+    ///
+    /// [`DefaultAnyResolver`]: struct.DefaultAnyResolver.html
+    pub fn fill_any(index: usize, tiles: &Vec<Tile>, width: usize) -> Tile {
+        DefaultAnyResolver.resolve(&TileContext::at(index, tiles, width))
+    }
+}
+
+/// Convert a tile style to SVG attributes, `None` when nothing would be visible.
+///
+/// The outline is scaled with the thickness of the beam, relative to the default of half a tile.
+fn svg_attr(style: TileStyle, scale: f32, thickness: f32) -> Option<Attr> {
+    let [red, green, blue, alpha] = style.fill;
+    if alpha == 0 {
+        return None;
+    }
+
+    let attr = Attr::default().fill(Color(red, green, blue));
+    let [red, green, blue, alpha] = style.stroke;
+
+    Some(if alpha == 0 || style.stroke_width <= 0.0 {
+        attr
+    } else {
+        attr.stroke(Color(red, green, blue))
+            .stroke_width(style.stroke_width * thickness * 2.0 / scale)
+    })
+}
+
+/// Canvas collecting SVG shapes.
+struct SvgCanvas<'a, F> {
+    figs: Vec<Fig>,
+    /// Amount of pixels everything is moved to the right & down.
+    offset: f32,
+    scale: f32,
+    theme: &'a TileTheme,
+    thickness_of: F,
+}
+
+impl<'a, F> SvgCanvas<'a, F>
+where
+    F: Fn(Tile) -> f32,
+{
+    /// Add a shape if the tile is visible.
+    fn push(&mut self, tile: Tile, fig: Fig) {
+        if let Some(attr) = svg_attr(
+            self.theme.style(tile),
+            self.scale,
+            (self.thickness_of)(tile),
+        ) {
+            self.figs.push(fig.styled(attr));
+        }
+    }
+}
+
+impl<'a, F> Canvas for SvgCanvas<'a, F>
+where
+    F: Fn(Tile) -> f32,
+{
+    fn fill_rect(&mut self, tile: Tile, x: f32, y: f32, width: f32, height: f32) {
+        let fig = Fig::Rect(x + self.offset, y + self.offset, width, height);
+        self.push(tile, fig);
+    }
+
+    fn draw_line(&mut self, tile: Tile, start: (f32, f32), end: (f32, f32), thickness: f32) {
+        let style = self.theme.style(tile);
+        let [red, green, blue, alpha] = style.solid();
+        if alpha == 0 {
+            return;
+        }
+
+        // A line only has an outline, so it's drawn in a single color
+        self.figs.push(
+            Fig::Line(
+                start.0 + self.offset,
+                start.1 + self.offset,
+                end.0 + self.offset,
+                end.1 + self.offset,
+            )
+            .styled(
+                Attr::default()
+                    .stroke(Color(red, green, blue))
+                    .stroke_width(thickness),
+            ),
+        );
+    }
+
+    fn draw_circle(&mut self, tile: Tile, center: (f32, f32), radius: f32) {
+        let fig = Fig::Circle(center.0 + self.offset, center.1 + self.offset, radius);
+        self.push(tile, fig);
+    }
+}
+
+/// Which of the eight neighbors of a tile are structural, so not empty.
+///
+/// Renderers can use this to choose the shape of a tile, like end caps for beams.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct TileContext {
+    /// Whether the neighbor above is structural.
+    pub up: bool,
+    /// Whether the neighbor below is structural.
+    pub down: bool,
+    /// Whether the neighbor to the left is structural.
+    pub left: bool,
+    /// Whether the neighbor to the right is structural.
+    pub right: bool,
+    /// Whether the diagonal neighbor to the top left is structural.
+    pub up_left: bool,
+    /// Whether the diagonal neighbor to the top right is structural.
+    pub up_right: bool,
+    /// Whether the diagonal neighbor to the bottom left is structural.
+    pub down_left: bool,
+    /// Whether the diagonal neighbor to the bottom right is structural.
+    pub down_right: bool,
+}
+
+impl TileContext {
+    /// The neighbors as bits, in row-major order starting with the top left neighbor as the
+    /// lowest bit.
+    pub fn mask(self) -> u8 {
+        [
+            self.up_left,
+            self.up,
+            self.up_right,
+            self.left,
+            self.right,
+            self.down_left,
+            self.down,
+            self.down_right,
+        ]
+        .iter()
+        .enumerate()
+        .filter(|(_, structural)| **structural)
+        .fold(0, |mask, (bit, _)| mask | 1 << bit)
+    }
+
+    /// Amount of the up, down, left & right neighbors that are structural.
+    pub fn straight_count(self) -> usize {
+        [self.up, self.down, self.left, self.right]
+            .iter()
+            .filter(|structural| **structural)
+            .count()
+    }
+
+    /// The context of a tile in a row-major slice of tiles.
+    ///
+    /// The tile can't be on the border, which is always the case in tiles padded with edges.
+    pub(crate) fn at(index: usize, tiles: &[Tile], width: usize) -> Self {
+        Self::from_fn(|x, y| {
+            !tiles[(index as isize + y as isize * width as isize + x as isize) as usize].is_empty()
+        })
+    }
+
+    /// Create the context from a function checking whether the neighbor at an offset is
+    /// structural.
+    fn from_fn<F>(is_structural: F) -> Self
+    where
+        F: Fn(i32, i32) -> bool,
+    {
+        Self {
+            up: is_structural(0, -1),
+            down: is_structural(0, 1),
+            left: is_structural(-1, 0),
+            right: is_structural(1, 0),
+            up_left: is_structural(-1, -1),
+            up_right: is_structural(1, -1),
+            down_left: is_structural(-1, 1),
+            down_right: is_structural(1, 1),
+        }
+    }
+}
+
+/// A wheel of an engine as a circle, in tiles.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WheelInfo {
+    /// The center of the circle, where `(0.0, 0.0)` is the top left of the engine.
+    pub center: (f32, f32),
+    /// The radius of the circle.
+    pub radius: f32,
+}
+
+impl WheelInfo {
+    /// Whether a point, in tiles, is inside of the circle.
+    pub fn contains(self, x: f32, y: f32) -> bool {
+        (x - self.center.0).powi(2) + (y - self.center.1).powi(2) <= self.radius.powi(2)
+    }
+}
+
+/// How much every part counts towards [`Engine::complexity_score`].
+///
+/// [`Engine::complexity_score`]: struct.Engine.html#method.complexity_score
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ComplexityWeights {
+    /// Weight of every distinct non-empty tile type.
+    pub tile_types: f32,
+    /// Weight of every place where a beam changes direction.
+    pub direction_changes: f32,
+    /// Weight of every wheel.
+    pub wheels: f32,
+}
+
+impl Default for ComplexityWeights {
+    fn default() -> Self {
+        Self {
+            tile_types: 1.0,
+            direction_changes: 0.5,
+            wheels: 2.0,
+        }
+    }
+}
+
+/// Direction of a diagonal beam.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum DiagDir {
+    /// `'/'`: connecting left bottom to right top.
+    Forward,
+    /// `'\'`: connecting left top to right bottom.
+    Back,
+}
+
+impl DiagDir {
+    /// The diagonal beam tile going in this direction.
+    pub fn to_tile(self) -> Tile {
+        match self {
+            DiagDir::Forward => Tile::diagonal_slash(),
+            DiagDir::Back => Tile::diagonal_backslash(),
+        }
+    }
+}
+
+/// Axis to mirror an engine over.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Axis {
+    /// Mirror left to right over a vertical line through the center.
+    Vertical,
+    /// Mirror top to bottom over a horizontal line through the center.
+    Horizontal,
+}
+
+impl FromStr for Tile {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // First attempt to convert it from a number
+        Ok(match s.parse::<u8>() {
+            Ok(num) => match FromPrimitive::from_u8(num) {
+                Some(tile) => tile,
+                None => {
+                    return Err(anyhow!("no tile with this number can be mapped"));
+                }
+            },
+            Err(err) => {
+                // TODO allow usage of letters and names and handle them here
+                return Err(anyhow!("{}", err));
+            }
+        })
+    }
+}
+
+/// The siege engine generator.
+pub struct Generator {
+    pub grid: Grid<Tile>,
+    pub overlapping_patterns: OverlappingPatterns<Tile>,
+    /// Fraction of the output rows from the top in which no wheels are allowed.
+    wheel_free_top: Option<f32>,
+    /// Position in the template of which the pattern is used for the top middle of the output.
+    top_mid: Coord,
+    /// Minimum fraction of the output the structure must span before an engine is accepted.
+    min_fill_ratio: Option<f32>,
+    /// Statistics of the patterns, cached because the WFC runners borrow them.
+    global_stats: GlobalStats,
+    /// Amount of patterns in the statistics, which can't be read back from them.
+    pattern_count: usize,
+    /// Strategy for replacing the `Tile::Any` tiles in the output.
+    any_resolver: Arc<dyn AnyResolver + Send + Sync>,
+}
+
+impl Generator {
+    /// Use a template from an array of numbers.
+    pub fn from_array(width: u32, height: u32, map: &[Tile]) -> Result<Self> {
+        Ok(Self::from_padded_grid(padded_grid(width, height, map)?))
+    }
+
+    /// Use a template from a few tiles at specific positions.
+    ///
+    /// All positions not in the list are filled with the `fill` tile, later tiles in the list
+    /// overwrite earlier ones at the same position.
+    pub fn from_coords(size: Size, tiles: &[(Coord, Tile)], fill: Tile) -> Result<Self> {
+        let mut map = vec![fill; (size.width() * size.height()) as usize];
+        for (coord, tile) in tiles.iter() {
+            if coord.x < 0
+                || coord.y < 0
+                || coord.x as u32 >= size.width()
+                || coord.y as u32 >= size.height()
+            {
+                return Err(anyhow!(
+                    "coordinate ({}, {}) is outside of the template",
+                    coord.x,
+                    coord.y
+                ));
+            }
+
+            map[(coord.y as u32 * size.width() + coord.x as u32) as usize] = *tile;
+        }
+
+        Self::from_array(size.width(), size.height(), &map)
+    }
+
+    /// Use multiple ASCII art templates at once.
+    ///
+    /// The patterns of all templates are combined into a single set. The frequency of each
+    /// pattern is summed over all templates, so bigger templates have more influence on the
+    /// output. The top middle of the output is always aligned with the first template.
+    ///
+    /// The symbols used are described in [`Tile`].
+    ///
+    /// [`Tile`]: enum.Tile.html
+    pub fn from_ascii_multi(templates: &[&str]) -> Result<Self> {
+        let grids = templates
+            .iter()
+            .map(|template| {
+                let (width, height, tiles) = parse_ascii(template)?;
+
+                padded_grid(width, height, &tiles[..])
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let first_width = grids
+            .first()
+            .ok_or_else(|| anyhow!("no templates passed"))?
+            .width();
+
+        // Place the padded grids next to each other, filling the space below the lower ones with
+        // edges
+        let width = grids.iter().map(|grid| grid.width()).sum();
+        let height = grids.iter().map(|grid| grid.height()).max().unwrap_or(0);
+        let grid = Grid::new_fn(Size::new(width, height), |coord| {
+            let mut x = coord.x as u32;
+            for grid in grids.iter() {
+                if x < grid.width() {
+                    return grid
+                        .get(Coord::new(x as i32, coord.y))
+                        .copied()
+                        .unwrap_or(Tile::Edge);
+                }
+
+                x -= grid.width();
+            }
+
+            Tile::Edge
+        });
+
+        Ok(Self {
+            top_mid: Coord::new(first_width as i32 / 2, 0),
+            ..Self::from_padded_grid(grid)
+        })
+    }
+
+    /// Extract the patterns from a grid that's already padded with edge tiles.
+    fn from_padded_grid(grid: Grid<Tile>) -> Self {
+        let overlapping_patterns = OverlappingPatterns::new_original_orientation(
+            grid.clone(),
+            NonZeroU32::new(PATTERN_SIZE as u32).unwrap(),
+        );
+        let pattern_descriptions = overlapping_patterns.pattern_descriptions();
+        let pattern_count = pattern_descriptions.len();
+        let global_stats = GlobalStats::new(pattern_descriptions);
+        debug!(
+            "loaded a {}x{} template with {} patterns",
+            grid.width() - 2,
+            grid.height() - 2,
+            pattern_count
+        );
+
+        Self {
+            top_mid: Coord::new(grid.width() as i32 / 2, 0),
+            global_stats,
+            pattern_count,
+            grid,
+            overlapping_patterns,
+            wheel_free_top: None,
+            min_fill_ratio: None,
+            any_resolver: Arc::new(DefaultAnyResolver),
+        }
+    }
+
+    /// Only allow wheels to be generated in the bottom part of the output.
+    ///
+    /// No wheels will appear in the top rows, `top_fraction` is the fraction of rows counted from
+    /// the top, so `0.6` on an output with a height of 10 only allows wheels in the bottom 4 rows.
+    pub fn with_wheels_at_bottom(mut self, top_fraction: f32) -> Self {
+        self.wheel_free_top = Some(top_fraction);
+
+        self
+    }
+
+    /// Fail when the template has more patterns than the maximum.
+    ///
+    /// Every pattern makes generating slower, so this catches templates that are accidentally too
+    /// big or varied before generating with them. The patterns are already extracted when the
+    /// generator is constructed, so the cap can't prevent the cost of extracting them.
+    pub fn with_max_patterns(self, max_patterns: usize) -> Result<Self> {
+        let pattern_count = self.pattern_count();
+        if pattern_count > max_patterns {
+            return Err(anyhow!(
+                "template has {} patterns, which is more than the maximum of {}",
+                pattern_count,
+                max_patterns
+            ));
+        }
+
+        Ok(self)
+    }
+
+    /// The size of the wave used internally to generate an output of the requested size.
+    ///
+    /// Generating surrounds the output with a border of a single tile on every side, so this is
+    /// the biggest amount of tiles any of the generate functions work with for that size.
+    pub fn max_output_size(&self, requested: Size) -> Size {
+        Size::new(requested.width() + 2, requested.height() + 2)
+    }
+
+    /// The size of the wave for the output dimensions, or `None` when either of them is zero.
+    ///
+    /// The WFC runner can't handle an empty output, so every generate function bails out early
+    /// through this.
+    pub(crate) fn wave_size(&self, output_width: u32, output_height: u32) -> Option<Size> {
+        let size = OutputSize::try_from((output_width, output_height)).ok()?;
+
+        Some(self.max_output_size(Size::new(size.width(), size.height())))
+    }
+
+    /// The amount of unique patterns extracted from the template.
+    pub fn pattern_count(&self) -> usize {
+        self.pattern_count
+    }
+
+    /// Reject engines of which the structure only fills a small part of the output.
+    ///
+    /// The bounding box of all tiles that aren't empty must cover at least `min_fill_ratio` of the
+    /// requested area, see [`Engine::fill_ratio`]. Rejected engines count as a failed attempt, so
+    /// they use up the retries just like contradictions do. This applies to every way of
+    /// generating, except for [`steps`] which only shows a single collapse.
+    ///
+    /// [`Engine::fill_ratio`]: struct.Engine.html#method.fill_ratio
+    /// [`steps`]: struct.Generator.html#method.steps
+    pub fn with_min_fill_ratio(mut self, min_fill_ratio: f32) -> Self {
+        self.min_fill_ratio = Some(min_fill_ratio);
+
+        self
+    }
+
+    /// Replace the `Tile::Any` tiles of generated engines with a custom strategy.
+    ///
+    /// By default the [`DefaultAnyResolver`] is used.
+    ///
+    /// [`DefaultAnyResolver`]: struct.DefaultAnyResolver.html
+    pub fn with_any_resolver<A>(mut self, resolver: A) -> Self
+    where
+        A: AnyResolver + Send + Sync + 'static,
+    {
+        self.any_resolver = Arc::new(resolver);
+
+        self
+    }
+
+    /// Use an ASCII art template.
+    ///
+    /// The symbols used are described in [`Tile`].
+    ///
+    /// [`Tile`]: enum.Tile.html
+    pub fn from_ascii<S>(ascii: S) -> Result<Self>
+    where
+        S: AsRef<str>,
+    {
+        let (width, height, tiles) = parse_ascii(ascii.as_ref())?;
+        trace!("parsed an ASCII template of {}x{} tiles", width, height);
+
+        Self::from_array(width, height, &tiles[..])
+    }
+
+    /// Use one of the built-in templates.
+    pub fn preset(preset: Preset) -> Self {
+        Self::from_ascii(preset.to_ascii()).unwrap()
+    }
+
+    /// Use a template from a path pointing to an ASCII file.
+    ///
+    /// The symbols used are described in [`Tile`]. Errors mention the path of the file and, for
+    /// characters that can't be parsed, the line & column in it.
+    ///
+    /// [`Tile`]: enum.Tile.html
+    pub fn from_ascii_file<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|| format!("could not open template file {}", path.display()))?;
+
+        Self::from_reader(file)
+            .with_context(|| format!("could not parse template file {}", path.display()))
+    }
+
+    /// Use a template from any source of ASCII text, like a file or a network stream.
+    ///
+    /// The symbols used are described in [`Tile`].
+    ///
+    /// [`Tile`]: enum.Tile.html
+    pub fn from_reader<R>(mut reader: R) -> Result<Self>
+    where
+        R: Read,
+    {
+        // Read everything
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+
+        // Parse it
+        Self::from_ascii(contents)
+    }
+
+    /// Generate a 2D grid with the output dimensions passed of a randomly generated siege engine.
+    pub fn generate_skeleton<R>(
+        &self,
+        output_width: u32,
+        output_height: u32,
+        retry_times: usize,
+        rng: &mut R,
+    ) -> Option<Engine>
+    where
+        R: Rng,
+    {
+        self.generate_skeleton_checked(
+            output_width,
+            output_height,
+            retry_times,
+            self.force_border_forbid(),
+            rng,
+        )
+    }
+
+    /// Generate a skeleton with dimensions that can't be zero.
+    ///
+    /// This is the same as [`generate_skeleton`], use `OutputSize::try_from((width, height))` to
+    /// check the dimensions up front.
+    ///
+    /// [`generate_skeleton`]: struct.Generator.html#method.generate_skeleton
+    pub fn generate_skeleton_sized<R>(
+        &self,
+        size: OutputSize,
+        retry_times: usize,
+        rng: &mut R,
+    ) -> Option<Engine>
+    where
+        R: Rng,
+    {
+        self.generate_skeleton(size.width(), size.height(), retry_times, rng)
+    }
+
+    /// Generate a skeleton into an existing engine, reusing it's buffer of tiles.
+    ///
+    /// This is the same as [`generate_skeleton`] but writes the result into the tiles of the
+    /// engine instead of allocating new ones. Collapsing the wave & resolving the any tiles still
+    /// allocate their own temporary buffers, so this only saves the allocation of the engine
+    /// itself. Returns `false` when generating failed, the engine is left in an unspecified state
+    /// then.
+    ///
+    /// [`generate_skeleton`]: struct.Generator.html#method.generate_skeleton
+    pub fn generate_skeleton_into<R>(
+        &self,
+        engine: &mut Engine,
+        output_width: u32,
+        output_height: u32,
+        retry_times: usize,
+        rng: &mut R,
+    ) -> bool
+    where
+        R: Rng,
+    {
+        self.generate_skeleton_with_forbid_into(
+            engine,
+            output_width,
+            output_height,
+            retry_times,
+            self.force_border_forbid(),
+            rng,
+        )
+    }
+
+    /// Generate the biggest skeleton that fits in the dimensions.
+    ///
+    /// When all retries fail both dimensions are shrunk by a single tile and it's tried again,
+    /// until either dimension reaches zero. Returns the engine with it's dimensions.
+    pub fn generate_skeleton_best_effort<R>(
+        &self,
+        max_width: u32,
+        max_height: u32,
+        retry_times: usize,
+        rng: &mut R,
+    ) -> Option<(Engine, (u32, u32))>
+    where
+        R: Rng,
+    {
+        (0..max_width.min(max_height)).find_map(|shrink| {
+            let (width, height) = (max_width - shrink, max_height - shrink);
+
+            self.generate_skeleton(width, height, retry_times, rng)
+                .map(|engine| (engine, (width, height)))
+        })
+    }
+
+    /// Generate a skeleton and center it in a bigger engine with the target size.
+    ///
+    /// The skeleton is generated with the output dimensions and isn't stretched, the space around
+    /// it is filled with empty tiles. When the space can't be split evenly the extra column or row
+    /// goes to the right or the bottom. Returns the engine with the position of the top left
+    /// corner of the skeleton in it, or `None` when generating failed or the skeleton doesn't fit
+    /// in the target size.
+    pub fn generate_skeleton_letterboxed<R>(
+        &self,
+        output_width: u32,
+        output_height: u32,
+        target: Size,
+        retry_times: usize,
+        rng: &mut R,
+    ) -> Option<(Engine, Coord)>
+    where
+        R: Rng,
+    {
+        let horizontal = target.width().checked_sub(output_width)?;
+        let vertical = target.height().checked_sub(output_height)?;
+
+        let engine = self.generate_skeleton(output_width, output_height, retry_times, rng)?;
+
+        let (left, top) = (horizontal / 2, vertical / 2);
+        let letterboxed = engine.pad(left, horizontal - left, top, vertical - top);
+
+        Some((letterboxed, Coord::new(left as i32, top as i32)))
+    }
+
+    /// Generate a skeleton in which some tiles never appear.
+    ///
+    /// All patterns starting with a forbidden tile are forbidden in every cell of the output.
+    /// Forbidding too much, like tiles that are needed to connect the rest of the template, will
+    /// cause every attempt to end in a contradiction. The `Tile::Any` tiles from the template are
+    /// resolved afterwards, so they can still become beams, crosses or walls.
+    pub fn generate_skeleton_forbidding<R>(
+        &self,
+        output_width: u32,
+        output_height: u32,
+        forbidden: &[Tile],
+        retry_times: usize,
+        rng: &mut R,
+    ) -> Option<Engine>
+    where
+        R: Rng,
+    {
+        let pattern_ids = self.pattern_ids_where(|top_left| forbidden.contains(&top_left));
+
+        let mut forbid = self.force_border_forbid();
+        // Offset the coordinates by the edge
+        for y in 1..=output_height as i32 {
+            for x in 1..=output_width as i32 {
+                let coord = Coord::new(x, y);
+                forbid
+                    .forbidden
+                    .extend(pattern_ids.iter().map(|pattern_id| (coord, *pattern_id)));
+            }
+        }
+
+        self.generate_skeleton_checked(output_width, output_height, retry_times, forbid, rng)
+    }
+
+    /// Generate a skeleton where some tiles are fixed before collapsing.
+    ///
+    /// Each constraint is a `(x, y, tile)` tuple in output coordinates, the cell at that position
+    /// is only allowed to collapse to patterns starting with the tile. The outer ring of the output
+    /// always borders the forced empty edges, so pinning a tile there that never touches an edge in
+    /// the template will make every attempt fail.
+    ///
+    /// Returns `None` when a constraint is out of bounds or when no attempt succeeded.
+    pub fn generate_skeleton_with_constraints<R>(
+        &self,
+        output_width: u32,
+        output_height: u32,
+        retry_times: usize,
+        rng: &mut R,
+        constraints: &[(u32, u32, Tile)],
+    ) -> Option<Engine>
+    where
+        R: Rng,
+    {
+        let mut forbid = self.force_border_forbid();
+        for &(x, y, tile) in constraints {
+            if x >= output_width || y >= output_height {
+                return None;
+            }
+
+            self.pin_tile(&mut forbid, x, y, tile);
+        }
+
+        self.generate_skeleton_checked(output_width, output_height, retry_times, forbid, rng)
+    }
+
+    /// Generate a skeleton where the structure is allowed to run into some of the sides.
+    ///
+    /// Normally every side of the output borders the empty edge of the template, so the structure
+    /// is always closed off like the template is. On an open side this border is also generated
+    /// as if it's part of the structure and then cut off, so for example a battering ram can be
+    /// open at the front.
+    ///
+    /// The corners are normally forced to be the corners of the template, this only happens for
+    /// corners where both sides are closed. The top middle is also only forced to match the
+    /// template when the top is closed. The output doesn't wrap around, otherwise an open side
+    /// would have to continue into the closed side opposite of it.
+    pub fn generate_skeleton_with_open_sides<R>(
+        &self,
+        output_width: u32,
+        output_height: u32,
+        open_sides: BorderSides,
+        retry_times: usize,
+        rng: &mut R,
+    ) -> Option<Engine>
+    where
+        R: Rng,
+    {
+        let mut forbid = self.force_border_forbid();
+        forbid.open_sides = open_sides;
+
+        self.collapse_retrying(
+            self.wave_size(output_width, output_height)?,
+            WrapNone,
+            forbid,
+            retry_times,
+            |wave| self.accepted_engine(&wave, output_width, output_height),
+            rng,
+        )
+    }
+
+    /// Generate a skeleton that can be repeated horizontally without seams.
+    ///
+    /// The left & right sides aren't bordered by the empty edge, instead the output wraps around
+    /// so the rightmost column connects to the leftmost column following the template. The top &
+    /// bottom are still closed.
+    pub fn generate_tileable_skeleton<R>(
+        &self,
+        output_width: u32,
+        output_height: u32,
+        retry_times: usize,
+        rng: &mut R,
+    ) -> Option<Engine>
+    where
+        R: Rng,
+    {
+        let wave_size = self.wave_size(output_width, output_height)?;
+
+        let mut forbid = self.force_border_forbid();
+        forbid.wraps_x = true;
+
+        let patterns = self.pattern_tiles();
+
+        // Only the top & bottom have a ring of edges
+        self.collapse_retrying(
+            Size::new(output_width, wave_size.height()),
+            WrapX,
+            forbid,
+            retry_times,
+            |wave| {
+                let pattern_id_at = |x: u32, y: u32| {
+                    wave.grid()
+                        .get_checked(Coord::new(x as i32, y as i32))
+                        .chosen_pattern_id()
+                        .unwrap()
+                };
+
+                // Verify that the seam between the rightmost & leftmost columns follows the
+                // template
+                let seamless = (1..=output_height).all(|y| {
+                    patterns::patterns_overlap(
+                        &patterns[pattern_id_at(output_width - 1, y) as usize],
+                        &patterns[pattern_id_at(0, y) as usize],
+                        Direction::East,
+                    )
+                });
+                if !seamless {
+                    return None;
+                }
+
+                let mut engine = Engine {
+                    width: output_width,
+                    height: output_height,
+                    tiles: (1..=output_height)
+                        .flat_map(|y| (0..output_width).map(move |x| (x, y)))
+                        .map(|(x, y)| {
+                            *self
+                                .overlapping_patterns
+                                .pattern_top_left_value(pattern_id_at(x, y))
+                        })
+                        .collect(),
+                };
+                engine.resolve_any_with(&*self.any_resolver);
+
+                self.accept_fill(engine)
+            },
+            rng,
+        )
+    }
+
+    /// Generate a skeleton with at least an amount of wheels on the bottom row.
+    ///
+    /// For every attempt the wheels are pinned at randomly chosen positions on the bottom row, so
+    /// positions that don't fit the template are only a problem for that single attempt.
+    ///
+    /// Returns `None` when the wheels don't fit on the bottom row or when no attempt succeeded.
+    pub fn generate_skeleton_with_min_wheels<R>(
+        &self,
+        output_width: u32,
+        output_height: u32,
+        min_wheels: usize,
+        retry_times: usize,
+        rng: &mut R,
+    ) -> Option<Engine>
+    where
+        R: Rng,
+    {
+        let wave_size = self.wave_size(output_width, output_height)?;
+        if min_wheels > output_width as usize {
+            return None;
+        }
+
+        (0..=retry_times).find_map(|_| {
+            // Pick new random positions for every attempt
+            let mut forbid = self.force_border_forbid();
+            for x in index::sample(rng, output_width as usize, min_wheels).into_iter() {
+                self.pin_tile(&mut forbid, x as u32, output_height - 1, Tile::Wheel);
+            }
+
+            self.collapse_retrying(
+                wave_size,
+                WrapXY,
+                forbid,
+                0,
+                |wave| self.accepted_engine(&wave, output_width, output_height),
+                rng,
+            )
+        })
+    }
+
+    /// Collapse a rectangular region of an engine again, keeping the rest of the engine intact.
+    ///
+    /// The region is a `(x, y, width, height)` tuple. All tiles outside the region are pinned to
+    /// patterns starting with their current tile or with an any tile, so the new tiles inside
+    /// always connect to the surrounding tiles following the template. Tiles that can't be found
+    /// in the template at all will make every attempt fail.
+    ///
+    /// Returns `None` when the region is out of bounds or when no attempt succeeded.
+    pub fn regenerate_region<R>(
+        &self,
+        engine: &Engine,
+        (region_x, region_y, region_width, region_height): (u32, u32, u32, u32),
+        retry_times: usize,
+        rng: &mut R,
+    ) -> Option<Engine>
+    where
+        R: Rng,
+    {
+        let end =
+            |start: u32, size: u32, max: u32| start.checked_add(size).filter(|end| *end <= max);
+        let region_end_x = end(region_x, region_width, engine.width)?;
+        let region_end_y = end(region_y, region_height, engine.height)?;
+
+        let is_outside = |index: usize| {
+            let x = index as u32 % engine.width;
+            let y = index as u32 / engine.width;
+
+            !(region_x..region_end_x).contains(&x) || !(region_y..region_end_y).contains(&y)
+        };
+
+        let mut forbid = self.force_border_forbid();
+        let mut forbidden_per_tile = HashMap::new();
+        for (index, tile) in engine.tiles.iter().enumerate() {
+            if !is_outside(index) {
+                continue;
+            }
+
+            // An any tile can also resolve into the original tile, the map is only used as a cache
+            // so it's order doesn't influence the order of the forbidden patterns
+            let pattern_ids = forbidden_per_tile.entry(*tile).or_insert_with(|| {
+                self.pattern_ids_where(|top_left| top_left != *tile && top_left != Tile::Any)
+            });
+
+            // Offset the coordinate by the edge
+            let coord = Coord::new(
+                (index as u32 % engine.width) as i32 + 1,
+                (index as u32 / engine.width) as i32 + 1,
+            );
+            forbid
+                .forbidden
+                .extend(pattern_ids.iter().map(|pattern_id| (coord, *pattern_id)));
+        }
+
+        let mut regenerated =
+            self.generate_skeleton_checked(engine.width, engine.height, retry_times, forbid, rng)?;
+
+        // The any tiles might've been resolved differently, so restore the tiles outside
+        for (index, tile) in engine.tiles.iter().enumerate() {
+            if is_outside(index) {
+                regenerated.tiles[index] = *tile;
+            }
+        }
+
+        Some(regenerated)
+    }
+
+    /// Generate a skeleton with a set amount of retries using a custom forbid pattern.
+    ///
+    /// The forbid pattern replaces the one that forces the border around the output to be empty,
+    /// so without forbidding the edge patterns inside the output yourself the output can contain
+    /// `Tile::Edge` tiles. The wave includes the border, so it's `output_width + 2` by
+    /// `output_height + 2` cells and the output starts at `(1, 1)`. Use [`pattern_ids_where`] &
+    /// the `overlapping_patterns` to find the pattern ids to forbid. The forbid pattern must be
+    /// `Send` & `Sync` because the WFC runner requires it.
+    ///
+    /// When forbidding runs into a contradiction the WFC runner resets the wave and collapses it
+    /// without the rest of the forbid pattern. This can't be detected for a custom forbid pattern,
+    /// so make sure it can't contradict itself.
+    ///
+    /// [`pattern_ids_where`]: struct.Generator.html#method.pattern_ids_where
+    pub fn generate_skeleton_with_forbid<F, R>(
+        &self,
+        output_width: u32,
+        output_height: u32,
+        retry_times: usize,
+        forbid: F,
+        rng: &mut R,
+    ) -> Option<Engine>
+    where
+        F: ForbidPattern + Clone + Send + Sync,
+        R: Rng,
+    {
+        self.generate_skeleton_checked(
+            output_width,
+            output_height,
+            retry_times,
+            UncheckedForbid(forbid),
+            rng,
+        )
+    }
+
+    /// Generate a skeleton using a forbid pattern that can be checked after collapsing.
+    fn generate_skeleton_checked<F, R>(
+        &self,
+        output_width: u32,
+        output_height: u32,
+        retry_times: usize,
+        forbid: F,
+        rng: &mut R,
+    ) -> Option<Engine>
+    where
+        F: CheckedForbid,
+        R: Rng,
+    {
+        self.collapse_retrying(
+            self.wave_size(output_width, output_height)?,
+            WrapXY,
+            forbid,
+            retry_times,
+            |wave| self.accepted_engine(&wave, output_width, output_height),
+            rng,
+        )
+    }
+
+    /// Generate a skeleton into an existing engine using the forbid pattern.
+    fn generate_skeleton_with_forbid_into<F, R>(
+        &self,
+        engine: &mut Engine,
+        output_width: u32,
+        output_height: u32,
+        retry_times: usize,
+        forbid: F,
+        rng: &mut R,
+    ) -> bool
+    where
+        F: CheckedForbid,
+        R: Rng,
+    {
+        let wave_size = match self.wave_size(output_width, output_height) {
+            Some(wave_size) => wave_size,
+            None => return false,
+        };
+
+        self.collapse_retrying(
+            wave_size,
+            WrapXY,
+            forbid,
+            retry_times,
+            |wave| {
+                self.write_wave(&wave, engine, output_width, output_height);
+
+                if self.fills_enough(engine) {
+                    Some(())
+                } else {
+                    None
+                }
+            },
+            rng,
+        )
+        .is_some()
+    }
+
+    /// Generate a skeleton, recovering from contradictions with a strategy.
+    ///
+    /// With [`RecoveryStrategy::LocalRetry`] the cells that were already collapsed far away from
+    /// the contradiction are kept for the next attempt, so less work is thrown away on big
+    /// outputs. When the next attempt runs into a contradiction right next to the kept cells
+    /// they're thrown away as well and it starts over. Every step clones the wave to find the
+    /// contradiction, so a local retry is slower per attempt than a full restart. Returns the
+    /// engine with the amount of attempts it took.
+    ///
+    /// [`RecoveryStrategy::LocalRetry`]: enum.RecoveryStrategy.html#variant.LocalRetry
+    pub fn generate_skeleton_with_recovery<R>(
+        &self,
+        output_width: u32,
+        output_height: u32,
+        retry_times: usize,
+        strategy: RecoveryStrategy,
+        rng: &mut R,
+    ) -> Option<(Engine, usize)>
+    where
+        R: Rng,
+    {
+        let size = self.wave_size(output_width, output_height)?;
+
+        let mut kept: Vec<(Coord, PatternId)> = vec![];
+        for attempt in 1..=retry_times + 1 {
+            let mut forbid = self.force_border_forbid();
+            forbid.kept = kept;
+
+            let mut run = RunOwn::new_forbid(size, &self.global_stats, forbid.clone(), rng);
+            let result = match strategy {
+                // The kept cells don't fit the border, so they can't be used again
+                _ if forbid.first_broken_rule(&run).is_some() => Err(None),
+                RecoveryStrategy::FullRestart => run.collapse(rng).map_err(|_| None),
+                // Finding the contradiction requires the state before the step that failed
+                RecoveryStrategy::LocalRetry { .. } => {
+                    collapse_tracked(&mut run, rng).map_err(Some)
+                }
+            };
+
+            kept = match (result, strategy) {
+                (Ok(()), _) => {
+                    match self.accepted_engine(&run.into_wave(), output_width, output_height) {
+                        Some(engine) => return Some((engine, attempt)),
+                        // Nothing went wrong, so there's nothing to keep either
+                        None => vec![],
+                    }
+                }
+                (Err(Some(before)), RecoveryStrategy::LocalRetry { radius }) => {
+                    let within = |coord: Coord, other: Coord, distance: i32| {
+                        (coord.x - other.x).abs() <= distance
+                            && (coord.y - other.y).abs() <= distance
+                    };
+
+                    match first_contradiction(&before) {
+                        // When the contradiction is next to a cell that was kept the kept cells
+                        // are part of the problem, so start over
+                        Some(contradiction)
+                            if !forbid
+                                .kept
+                                .iter()
+                                .any(|(coord, _)| within(*coord, contradiction, 1)) =>
+                        {
+                            // Keep all collapsed cells outside of the window around the
+                            // contradiction
+                            before
+                                .wave_cell_ref_enumerate()
+                                .filter(|(coord, _)| !within(*coord, contradiction, radius as i32))
+                                .filter_map(|(coord, cell)| {
+                                    cell.chosen_pattern_id()
+                                        .ok()
+                                        .map(|pattern_id| (coord, pattern_id))
+                                })
+                                .collect()
+                        }
+                        _ => vec![],
+                    }
+                }
+                (Err(_), _) => vec![],
+            };
+        }
+
+        None
+    }
+
+    /// Generate a skeleton, retrying until it succeeds or the timeout has passed.
+    ///
+    /// The clock is only checked in between attempts, so a single slow attempt can exceed the
+    /// timeout. Returns `None` when no attempt succeeded before the deadline.
+    pub fn generate_skeleton_timeout<R>(
+        &self,
+        output_width: u32,
+        output_height: u32,
+        timeout: Duration,
+        rng: &mut R,
+    ) -> Option<Engine>
+    where
+        R: Rng,
+    {
+        let deadline = Instant::now() + timeout;
+        let wave_size = self.wave_size(output_width, output_height)?;
+
+        let forbid = self.force_border_forbid();
+        while Instant::now() < deadline {
+            let engine = self.collapse_retrying(
+                wave_size,
+                WrapXY,
+                forbid.clone(),
+                0,
+                |wave| self.accepted_engine(&wave, output_width, output_height),
+                rng,
+            );
+
+            if engine.is_some() {
+                return engine;
+            }
+        }
+
+        None
+    }
+
+    /// Check whether the template can produce an engine with the output dimensions.
+    ///
+    /// This tries at most `sample_attempts` collapses and stops at the first one that succeeds, a
+    /// `false` result means it's unlikely but not impossible for the template to succeed.
+    pub fn can_generate<R>(
+        &self,
+        output_width: u32,
+        output_height: u32,
+        sample_attempts: usize,
+        rng: &mut R,
+    ) -> bool
+    where
+        R: Rng,
+    {
+        let wave_size = match self.wave_size(output_width, output_height) {
+            Some(wave_size) => wave_size,
+            None => return false,
+        };
+
+        sample_attempts > 0
+            && self
+                .collapse_retrying(
+                    wave_size,
+                    WrapXY,
+                    self.force_border_forbid(),
+                    sample_attempts - 1,
+                    |wave| self.accepted_engine(&wave, output_width, output_height),
+                    rng,
+                )
+                .is_some()
+    }
+
+    /// Estimate the fraction of collapses that succeed for the output dimensions.
+    ///
+    /// All `sample_attempts` collapses are always tried.
+    pub fn success_rate<R>(
+        &self,
+        output_width: u32,
+        output_height: u32,
+        sample_attempts: usize,
+        rng: &mut R,
+    ) -> f32
+    where
+        R: Rng,
+    {
+        let wave_size = match self.wave_size(output_width, output_height) {
+            Some(wave_size) if sample_attempts > 0 => wave_size,
+            _ => return 0.0,
+        };
+
+        let forbid = self.force_border_forbid();
+
+        let successes = (0..sample_attempts)
+            .filter(|_| {
+                self.collapse_retrying(
+                    wave_size,
+                    WrapXY,
+                    forbid.clone(),
+                    0,
+                    |wave| self.accepted_engine(&wave, output_width, output_height),
+                    rng,
+                )
+                .is_some()
+            })
+            .count();
+
+        successes as f32 / sample_attempts as f32
+    }
+
+    /// Run a single collapse attempt and report the state of every cell in the wave.
+    ///
+    /// This doesn't retry, so when a contradiction occurs the report can be used to find out
+    /// where it happened. wfc resets the wave after a contradiction, so the report holds the state
+    /// right before the step that failed, with the cell that step collapsed as the contradiction.
+    /// When forcing the border already failed the first cell breaking the border is reported
+    /// instead. Every step clones the wave, so this is slower than a normal generation. When
+    /// either dimension is zero the report is empty.
+    pub fn generate_report<R>(
+        &self,
+        output_width: u32,
+        output_height: u32,
+        rng: &mut R,
+    ) -> CollapseReport
+    where
+        R: Rng,
+    {
+        let wave_size = match self.wave_size(output_width, output_height) {
+            Some(wave_size) => wave_size,
+            // There's nothing to collapse
+            None => {
+                return CollapseReport {
+                    width: 0,
+                    height: 0,
+                    cells: vec![],
+                    engine: None,
+                }
+            }
+        };
+
+        // Construct the WFC runner
+        let forbid = self.force_border_forbid();
+        let mut run = RunOwn::new_forbid(wave_size, &self.global_stats, forbid.clone(), rng);
+
+        // Collapse only once, keeping the state before the contradiction
+        let (state, contradiction) = match forbid.first_broken_rule(&run) {
+            Some(coord) => (run, Some(coord)),
+            None => match collapse_tracked(&mut run, rng) {
+                Ok(()) => (run, None),
+                Err(before) => {
+                    let contradiction = first_contradiction(&before);
+
+                    (*before, contradiction)
+                }
+            },
+        };
+
+        let cells = state
+            .wave_cell_ref_enumerate()
+            .map(|(coord, cell)| match cell.chosen_pattern_id() {
+                _ if Some(coord) == contradiction => CellState::Contradiction,
+                Ok(pattern_id) => CellState::Collapsed(pattern_id),
+                Err(ChosenPatternIdError::MultipleCompatiblePatterns) => CellState::Undecided,
+                Err(ChosenPatternIdError::NoCompatiblePatterns) => CellState::Contradiction,
+            })
+            .collect();
+
+        let engine = match contradiction {
+            None => self.accepted_engine(&state.into_wave(), output_width, output_height),
+            Some(_) => None,
+        };
+
+        CollapseReport {
+            width: wave_size.width(),
+            height: wave_size.height(),
+            cells,
+            engine,
+        }
+    }
+
+    /// Generate a skeleton without resolving the any tiles.
+    ///
+    /// The `Tile::Any` tiles are kept as they are, use [`Engine::resolve_any`] to fill them in
+    /// with the same heuristic as [`generate_skeleton`].
+    ///
+    /// [`Engine::resolve_any`]: struct.Engine.html#method.resolve_any
+    /// [`generate_skeleton`]: struct.Generator.html#method.generate_skeleton
+    pub fn generate_skeleton_raw<R>(
+        &self,
+        output_width: u32,
+        output_height: u32,
+        retry_times: usize,
+        rng: &mut R,
+    ) -> Option<Engine>
+    where
+        R: Rng,
+    {
+        // Attempt to collapse with a set amount of retries
+        self.collapse_retrying(
+            self.wave_size(output_width, output_height)?,
+            WrapXY,
+            self.force_border_forbid(),
+            retry_times,
+            |wave| self.accept_fill(self.raw_engine_from_wave(&wave, output_width, output_height)),
+            rng,
+        )
+    }
+
+    /// Generate a skeleton including the ring of edge tiles around the output.
+    ///
+    /// The returned engine is `output_width + 2` by `output_height + 2` tiles, with the cells
+    /// forced by the border forbid shown as `'~'` when converted to ASCII. This is meant for
+    /// debugging, the engine can't be used as a template.
+    pub fn generate_skeleton_with_edges<R>(
+        &self,
+        output_width: u32,
+        output_height: u32,
+        retry_times: usize,
+        rng: &mut R,
+    ) -> Option<Engine>
+    where
+        R: Rng,
+    {
+        let wave_size = self.wave_size(output_width, output_height)?;
+        let (width, height) = (wave_size.width(), wave_size.height());
+
+        // Attempt to collapse with a set amount of retries
+        let (wave, inner) = self.collapse_retrying(
+            wave_size,
+            WrapXY,
+            self.force_border_forbid(),
+            retry_times,
+            |wave| {
+                // Resolve the any tiles of the inner part like a normal generation would
+                let inner = self.accepted_engine(&wave, output_width, output_height)?;
+
+                Some((wave, inner))
+            },
+            rng,
+        )?;
+
+        let tiles = wave
+            .grid()
+            .enumerate()
+            .map(|(coord, cell)| {
+                if coord_is_edge(coord, width as i32, height as i32) {
+                    let pattern_id = cell.chosen_pattern_id().unwrap();
+                    *self.overlapping_patterns.pattern_top_left_value(pattern_id)
+                } else {
+                    inner.tiles[inner.index(coord.x as u32 - 1, coord.y as u32 - 1).unwrap()]
+                }
+            })
+            .collect();
+
+        Some(Engine {
+            tiles,
+            width,
+            height,
+        })
+    }
+
+    /// Generate a skeleton and return the chosen pattern of every cell alongside the engine.
+    ///
+    /// The pattern ids are for the whole wave in row-major order, including the ring of edge tiles
+    /// around the output, so the vector has `(output_width + 2) * (output_height + 2)` items.
+    pub fn generate_skeleton_with_pattern_ids<R>(
+        &self,
+        output_width: u32,
+        output_height: u32,
+        retry_times: usize,
+        rng: &mut R,
+    ) -> Option<(Engine, Vec<PatternId>)>
+    where
+        R: Rng,
+    {
+        // Attempt to collapse with a set amount of retries
+        self.collapse_retrying(
+            self.wave_size(output_width, output_height)?,
+            WrapXY,
+            self.force_border_forbid(),
+            retry_times,
+            |wave| {
+                let engine = self.accepted_engine(&wave, output_width, output_height)?;
+
+                let pattern_ids = wave
+                    .grid()
+                    .iter()
+                    .map(|cell| cell.chosen_pattern_id().unwrap())
+                    .collect();
+
+                Some((engine, pattern_ids))
+            },
+            rng,
+        )
+    }
+
+    /// Generate a skeleton with a random seed and return the seed alongside the engine.
+    ///
+    /// The seed is drawn from the passed random number generator and used to drive a seeded
+    /// generator, so passing it to [`generate_skeleton_from_seed`] reproduces the same engine.
+    ///
+    /// [`generate_skeleton_from_seed`]: struct.Generator.html#method.generate_skeleton_from_seed
+    pub fn generate_skeleton_with_seed<R>(
+        &self,
+        output_width: u32,
+        output_height: u32,
+        retry_times: usize,
+        rng: &mut R,
+    ) -> Option<(Engine, u64)>
+    where
+        R: Rng,
+    {
+        let seed = rng.gen();
+
+        self.generate_skeleton_from_seed(output_width, output_height, retry_times, seed)
+            .map(|engine| (engine, seed))
+    }
+
+    /// Generate a skeleton deterministically from a seed.
+    ///
+    /// The same template, dimensions & seed always result in the same engine: the patterns are
+    /// forbidden in a fixed order and the seeded generator is the only source of randomness.
+    /// Generators created from the same template get the same pattern ids, so the seed can also
+    /// be used with a different instance of the generator.
+    ///
+    /// There are still some things outside of the control of this crate that can cause a
+    /// different result for the same seed: `StdRng` can change it's algorithm between versions of
+    /// `rand`, and `wfc` picks the next cell to collapse based on floating point entropy, which
+    /// isn't guaranteed to be rounded the same on every platform when two cells are very close.
+    pub fn generate_skeleton_from_seed(
+        &self,
+        output_width: u32,
+        output_height: u32,
+        retry_times: usize,
+        seed: u64,
+    ) -> Option<Engine> {
+        self.generate_skeleton(
+            output_width,
+            output_height,
+            retry_times,
+            &mut StdRng::seed_from_u64(seed),
+        )
+    }
+
+    /// Generate a batch of skeletons from a single seed.
+    ///
+    /// The seed of every item is derived from the master seed & it's index with [`derive_seed`],
+    /// so any item can also be reproduced on it's own with [`generate_skeleton_from_seed`]. Items
+    /// for which the generation failed are `None`.
+    ///
+    /// [`derive_seed`]: fn.derive_seed.html
+    /// [`generate_skeleton_from_seed`]: struct.Generator.html#method.generate_skeleton_from_seed
+    pub fn generate_batch(
+        &self,
+        output_width: u32,
+        output_height: u32,
+        retry_times: usize,
+        master_seed: u64,
+        amount: usize,
+    ) -> Vec<Option<Engine>> {
+        (0..amount as u64)
+            .map(|index| {
+                self.generate_skeleton_from_seed(
+                    output_width,
+                    output_height,
+                    retry_times,
+                    derive_seed(master_seed, index),
+                )
+            })
+            .collect()
+    }
+
+    /// Collapse the wave with the forbid pattern, starting over until an attempt is accepted.
+    ///
+    /// Every generation ends up here, so all attempts are logged the same way. The collapsed wave
+    /// is converted to the result with `finish`, which rejects the attempt by returning `None`.
+    fn collapse_retrying<W, F, R, T, C>(
+        &self,
+        wave_size: Size,
+        wrap: W,
+        forbid: F,
+        retry_times: usize,
+        mut finish: C,
+        rng: &mut R,
+    ) -> Option<T>
+    where
+        W: Wrap,
+        F: CheckedForbid,
+        R: Rng,
+        C: FnMut(Wave) -> Option<T>,
+    {
+        for attempt in 1..=retry_times + 1 {
+            trace!(
+                "attempt {} of {} to collapse a {}x{} wave",
+                attempt,
+                retry_times + 1,
+                wave_size.width(),
+                wave_size.height()
+            );
+
+            let mut run =
+                RunOwn::new_wrap_forbid(wave_size, &self.global_stats, wrap, forbid.clone(), rng);
+
+            // Finding the contradiction is slow, so it's only done when it will be logged
+            if let Err(contradiction) =
+                collapse_checked(&mut run, &forbid, log_enabled!(Level::Debug), rng)
+            {
+                match contradiction {
+                    Some(coord) => debug!(
+                        "attempt {} ended in a contradiction at ({}, {}) of the wave",
+                        attempt, coord.x, coord.y
+                    ),
+                    None => debug!("attempt {} ended in a contradiction", attempt),
+                }
+
+                continue;
+            }
+
+            match finish(run.into_wave()) {
+                Some(result) => {
+                    debug!(
+                        "collapsed a {}x{} wave in {} attempts",
+                        wave_size.width(),
+                        wave_size.height(),
+                        attempt
+                    );
+
+                    return Some(result);
+                }
+                None => debug!("attempt {} was rejected", attempt),
+            }
+        }
+
+        debug!(
+            "failed to collapse a {}x{} wave in {} attempts",
+            wave_size.width(),
+            wave_size.height(),
+            retry_times + 1
+        );
+
+        None
+    }
+
+    /// Convert a fully collapsed wave to an engine, `None` when it doesn't fill enough.
+    fn accepted_engine(
+        &self,
+        wave: &Wave,
+        output_width: u32,
+        output_height: u32,
+    ) -> Option<Engine> {
+        self.accept_fill(self.engine_from_wave(wave, output_width, output_height))
+    }
+
+    /// Keep the engine only when it fills enough of it's area, see [`with_min_fill_ratio`].
+    ///
+    /// [`with_min_fill_ratio`]: struct.Generator.html#method.with_min_fill_ratio
+    fn accept_fill(&self, engine: Engine) -> Option<Engine> {
+        if self.fills_enough(&engine) {
+            Some(engine)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the engine fills enough of it's area, see [`with_min_fill_ratio`].
+    ///
+    /// [`with_min_fill_ratio`]: struct.Generator.html#method.with_min_fill_ratio
+    fn fills_enough(&self, engine: &Engine) -> bool {
+        match self.min_fill_ratio {
+            Some(min_fill_ratio) if engine.fill_ratio() < min_fill_ratio => {
+                debug!(
+                    "fill ratio {} is below {}",
+                    engine.fill_ratio(),
+                    min_fill_ratio
+                );
+
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Convert a fully collapsed wave to an engine.
+    fn engine_from_wave(&self, wave: &Wave, output_width: u32, output_height: u32) -> Engine {
+        let mut engine = Engine::default();
+        self.write_wave(wave, &mut engine, output_width, output_height);
+
+        engine
+    }
+
+    /// Convert a fully collapsed wave to an engine without resolving the any tiles.
+    fn raw_engine_from_wave(&self, wave: &Wave, output_width: u32, output_height: u32) -> Engine {
+        let mut engine = Engine::default();
+        self.write_raw_wave(wave, &mut engine, output_width, output_height);
+
+        engine
+    }
+
+    /// Overwrite an engine with a fully collapsed wave.
+    fn write_wave(&self, wave: &Wave, engine: &mut Engine, output_width: u32, output_height: u32) {
+        self.write_raw_wave(wave, engine, output_width, output_height);
+        engine.resolve_any_with(&*self.any_resolver);
+    }
+
+    /// Overwrite an engine with a fully collapsed wave without resolving the any tiles.
+    fn write_raw_wave(
+        &self,
+        wave: &Wave,
+        engine: &mut Engine,
+        output_width: u32,
+        output_height: u32,
+    ) {
+        // Map the result to indices of the original vector, reusing the buffer of the engine
+        engine.tiles.clear();
+        engine.tiles.extend(
+            wave.grid()
+                .enumerate()
+                // Remove the border, which can also contain structure when a side is open
+                .filter(|(coord, _)| {
+                    !coord_is_edge(*coord, output_width as i32 + 2, output_height as i32 + 2)
+                })
+                .map(|(_, cell)| {
+                    // Get the original index
+                    let pattern_id = cell.chosen_pattern_id().unwrap();
+                    *self.overlapping_patterns.pattern_top_left_value(pattern_id)
+                }),
+        );
+        engine.width = output_width;
+        engine.height = output_height;
+    }
+
+    /// Create the forbid pattern.
+    fn force_border_forbid(&self) -> ForceBorderForbid {
+        let size = self.grid.size();
+        let width = size.width() as i32;
+        let height = size.height() as i32;
+
+        let overlapping_grid = self.overlapping_patterns.id_grid_original_orientation();
+
+        // Always let the corner be empty tiles
+        let top_left = *overlapping_grid.get_checked(Coord::new(0, 0));
+        let bot_left = *overlapping_grid.get_checked(Coord::new(0, height - 1));
+        let top_right = *overlapping_grid.get_checked(Coord::new(width - 1, 0));
+        let bot_right = *overlapping_grid.get_checked(Coord::new(width - 1, height - 1));
+
+        // Special one for the middle of the top so it's height matches
+        let top_mid = *overlapping_grid.get_checked(self.top_mid);
+
+        // Get all the patterns containing empty tiles, these are not only found on the border
+        // when multiple templates are combined
+        let pattern_ids = self.pattern_ids_where(|top_left| top_left == Tile::Edge);
+
+        ForceBorderForbid {
+            pattern_ids,
+            top_left,
+            bot_left,
+            top_right,
+            bot_right,
+            top_mid,
+            forbidden: vec![],
+            open_sides: BorderSides::default(),
+            wraps_x: false,
+            kept: vec![],
+            wheel_free_top: self.wheel_free_top.map(|top_fraction| {
+                (
+                    top_fraction,
+                    self.pattern_ids_where(|top_left| top_left == Tile::Wheel),
+                )
+            }),
+        }
+    }
+
+    /// Only allow patterns starting with the tile at the position in output coordinates.
+    fn pin_tile(&self, forbid: &mut ForceBorderForbid, x: u32, y: u32, tile: Tile) {
+        // Offset the coordinate by the edge
+        let coord = Coord::new(x as i32 + 1, y as i32 + 1);
+
+        forbid.forbidden.extend(
+            self.pattern_ids_where(|top_left| top_left != tile)
+                .into_iter()
+                .map(|pattern_id| (coord, pattern_id)),
+        );
+    }
+
+    /// All patterns of which the top left tile matches the predicate.
+    ///
+    /// The ids are sorted so forbidding them always happens in the same order.
+    pub fn pattern_ids_where<P>(&self, predicate: P) -> Vec<PatternId>
+    where
+        P: Fn(Tile) -> bool,
+    {
+        let num_patterns = self.pattern_count();
+
+        (0..num_patterns as PatternId)
+            .filter(|pattern_id| {
+                predicate(
+                    *self
+                        .overlapping_patterns
+                        .pattern_top_left_value(*pattern_id),
+                )
+            })
+            .collect()
+    }
+}
+
+impl Clone for Generator {
+    fn clone(&self) -> Self {
+        // The overlapping patterns can't be cloned, so extract them again from the grid
+        Self {
+            wheel_free_top: self.wheel_free_top,
+            top_mid: self.top_mid,
+            min_fill_ratio: self.min_fill_ratio,
+            any_resolver: self.any_resolver.clone(),
+            ..Self::from_padded_grid(self.grid.clone())
+        }
+    }
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Self::preset(Preset::Default)
+    }
+}
+
+/// Built-in templates, see [`Generator::preset`].
+///
+/// [`Generator::preset`]: struct.Generator.html#method.preset
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Preset {
+    /// The template used by `Generator::default()`.
+    Default,
+    /// A long engine with a ram sticking out of the front.
+    BatteringRam,
+    /// An engine with an arm sloping upwards.
+    Catapult,
+    /// A tall siege tower with battlements on top.
+    Tower,
+}
+
+impl Preset {
+    /// All presets.
+    pub const ALL: [Preset; 4] = [
+        Preset::Default,
+        Preset::BatteringRam,
+        Preset::Catapult,
+        Preset::Tower,
+    ];
+
+    /// The ASCII template of the preset.
+    pub fn to_ascii(self) -> &'static str {
+        match self {
+            Preset::Default => include_str!("default.ascii"),
+            Preset::BatteringRam => include_str!("battering_ram.ascii"),
+            Preset::Catapult => include_str!("catapult.ascii"),
+            Preset::Tower => include_str!("tower.ascii"),
+        }
+    }
+}
+
+/// The state of a single cell in the wave after a collapse attempt.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CellState {
+    /// Only a single pattern is possible for this cell.
+    Collapsed(PatternId),
+    /// Multiple patterns are still possible for this cell.
+    Undecided,
+    /// The cell that caused the generation to fail.
+    Contradiction,
+}
+
+/// The result of a single collapse attempt, see [`Generator::generate_report`].
+///
+/// The cells are reported for the whole wave, including the ring of edge tiles around the output,
+/// so every coordinate is offset by one compared to the engine.
+///
+/// [`Generator::generate_report`]: struct.Generator.html#method.generate_report
+#[derive(Debug)]
+pub struct CollapseReport {
+    width: u32,
+    height: u32,
+    cells: Vec<CellState>,
+    engine: Option<Engine>,
+}
+
+impl CollapseReport {
+    /// The generated engine, `None` when a contradiction occurred.
+    pub fn engine(&self) -> Option<&Engine> {
+        self.engine.as_ref()
+    }
+
+    /// The state of all cells in the wave as a one dimensional vector.
+    pub fn cells(&self) -> &Vec<CellState> {
+        &self.cells
+    }
+
+    /// The coordinates of all cells that caused the generation to fail.
+    pub fn contradictions(&self) -> Vec<Coord> {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| **cell == CellState::Contradiction)
+            .map(|(index, _)| {
+                Coord::new(
+                    (index % self.width as usize) as i32,
+                    (index / self.width as usize) as i32,
+                )
+            })
+            .collect()
+    }
+
+    /// Amount of cells in a row of the wave, including the edges.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Amount of cells in a column of the wave, including the edges.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
+
+/// Dimensions of a generated engine, which are never zero.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct OutputSize {
+    width: NonZeroU32,
+    height: NonZeroU32,
+}
+
+impl OutputSize {
+    /// Construct the size from dimensions that are already checked.
+    pub fn new(width: NonZeroU32, height: NonZeroU32) -> Self {
+        Self { width, height }
+    }
+
+    /// The amount of tiles horizontally.
+    pub fn width(self) -> u32 {
+        self.width.get()
+    }
+
+    /// The amount of tiles vertically.
+    pub fn height(self) -> u32 {
+        self.height.get()
+    }
+}
+
+impl TryFrom<(u32, u32)> for OutputSize {
+    type Error = Error;
+
+    fn try_from((width, height): (u32, u32)) -> Result<Self, Self::Error> {
+        match (NonZeroU32::new(width), NonZeroU32::new(height)) {
+            (Some(width), Some(height)) => Ok(Self { width, height }),
+            _ => Err(anyhow!("output size {}x{} can't be zero", width, height)),
+        }
+    }
+}
+
+/// How to continue after a collapse runs into a contradiction.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum RecoveryStrategy {
+    /// Start over with an empty wave.
+    FullRestart,
+    /// Start over but keep all collapsed cells further than `radius` cells away from the
+    /// contradiction.
+    LocalRetry {
+        /// The amount of cells around the contradiction that are collapsed again.
+        radius: u32,
+    },
+}
+
+/// A forbid pattern of which it can be checked whether the rules are applied to a run.
+///
+/// When forbidding runs into a contradiction wfc resets the wave and continues without the
+/// remaining rules, so the run will collapse as if there was no forbid pattern at all.
+trait CheckedForbid: ForbidPattern + Clone + Send + Sync {
+    /// The first cell of the run in which the rules aren't applied.
+    fn first_broken_rule<W: Wrap>(&self, run: &RunOwn<'_, W, Self>) -> Option<Coord>;
+}
+
+/// Sides of the output, used to choose which ones are open.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct BorderSides {
+    /// Whether the top side is open.
+    pub top: bool,
+    /// Whether the bottom side is open.
+    pub bottom: bool,
+    /// Whether the left side is open.
+    pub left: bool,
+    /// Whether the right side is open.
+    pub right: bool,
+}
+
+/// Ensures that the the edges will always be filled with empty tiles.
+#[derive(Clone)]
+struct ForceBorderForbid {
+    pattern_ids: Vec<PatternId>,
+    top_left: PatternId,
+    bot_left: PatternId,
+    top_right: PatternId,
+    bot_right: PatternId,
+    top_mid: PatternId,
+    /// Extra patterns that are not allowed at a specific coordinate in the wave.
+    forbidden: Vec<(Coord, PatternId)>,
+    /// Sides where the border isn't forced to be the empty edge.
+    open_sides: BorderSides,
+    /// Whether the wave wraps around horizontally without a border on the left & right sides.
+    wraps_x: bool,
+    /// Patterns that are the only ones allowed at a specific coordinate in the wave.
+    kept: Vec<(Coord, PatternId)>,
+    /// Fraction of the output rows from the top with the wheel patterns not allowed there.
+    wheel_free_top: Option<(f32, Vec<PatternId>)>,
+}
+
+/// A single rule of the forbid pattern for a cell of the wave.
+#[derive(Debug, Copy, Clone)]
+enum ForbidRule {
+    /// The pattern is the only one allowed at the coordinate.
+    Keep(Coord, PatternId),
+    /// The pattern is not allowed at the coordinate.
+    Forbid(Coord, PatternId),
+}
+
+impl ForceBorderForbid {
+    /// Forbid the patterns, stopping at the first contradiction.
+    fn try_forbid<W: Wrap, R: Rng>(
+        &self,
+        fi: &mut ForbidInterface<W>,
+        rng: &mut R,
+    ) -> Result<(), PropagateError> {
+        let wave_size = fi.wave_size();
+
+        self.try_for_each_rule(wave_size, |rule| match rule {
+            ForbidRule::Keep(coord, pattern_id) => {
+                fi.forbid_all_patterns_except(coord, pattern_id, rng)
+            }
+            ForbidRule::Forbid(coord, pattern_id) => fi.forbid_pattern(coord, pattern_id, rng),
+        })
+    }
+
+    /// Apply all rules for a wave of the size in order, stopping at the first error.
+    fn try_for_each_rule<E, A>(&self, wave_size: Size, mut apply: A) -> Result<(), E>
+    where
+        A: FnMut(ForbidRule) -> Result<(), E>,
+    {
+        let width = wave_size.width() as i32;
+        let height = wave_size.height() as i32;
+
+        // A wave that wraps around horizontally has no left & right border at all
+        let closed_top = !self.open_sides.top;
+        let closed_bottom = !self.open_sides.bottom;
+        let closed_left = !self.open_sides.left && !self.wraps_x;
+        let closed_right = !self.open_sides.right && !self.wraps_x;
+
+        // Only force the corners & the top middle when they border closed sides
+        let pins = [
+            (closed_top && closed_left, Coord::new(0, 0), self.top_left),
+            (
+                closed_bottom && closed_left,
+                Coord::new(0, height - 1),
+                self.bot_left,
+            ),
+            (
+                closed_top && closed_right,
+                Coord::new(width - 1, 0),
+                self.top_right,
+            ),
+            (
+                closed_bottom && closed_right,
+                Coord::new(width - 1, height - 1),
+                self.bot_right,
+            ),
+            (closed_top, Coord::new(width / 2, 0), self.top_mid),
+        ];
+        for &(closed, coord, pattern_id) in pins.iter() {
+            if closed {
+                apply(ForbidRule::Keep(coord, pattern_id))?;
+            }
+        }
+
+        // Iterate in row-major order, the same order in which the wave is stored
+        for y in 0..height {
+            for x in 0..width {
+                let coord = Coord::new(x, y);
+                // The ends of an open side also lie on the closed side next to it, so they stay
+                // free to become an edge like the rest of that closed side
+                let on_closed_side = (closed_top && y == 0)
+                    || (closed_bottom && y == height - 1)
+                    || (closed_left && x == 0)
+                    || (closed_right && x == width - 1);
+                if !on_closed_side {
+                    // The inside & the open sides are never allowed to be an edge
+                    for pattern_id in self.pattern_ids.iter() {
+                        apply(ForbidRule::Forbid(coord, *pattern_id))?;
+                    }
+                }
+            }
+        }
+
+        for (coord, pattern_id) in self.forbidden.iter() {
+            apply(ForbidRule::Forbid(*coord, *pattern_id))?;
+        }
+
+        for (coord, pattern_id) in self.kept.iter() {
+            apply(ForbidRule::Keep(*coord, *pattern_id))?;
+        }
+
+        if let Some((top_fraction, wheel_pattern_ids)) = &self.wheel_free_top {
+            // The output is surrounded by the edges
+            let rows = ((height - 2) as f32 * top_fraction).floor() as i32;
+
+            for y in 1..=rows {
+                for x in 1..width - 1 {
+                    for pattern_id in wheel_pattern_ids.iter() {
+                        apply(ForbidRule::Forbid(Coord::new(x, y), *pattern_id))?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ForbidPattern for ForceBorderForbid {
+    fn forbid<W: Wrap, R: Rng>(&mut self, fi: &mut ForbidInterface<W>, rng: &mut R) {
+        // wfc resets the wave after a contradiction, the broken rules are checked after collapsing
+        let _ = self.try_forbid(fi, rng);
+    }
+}
+
+impl CheckedForbid for ForceBorderForbid {
+    fn first_broken_rule<W: Wrap>(&self, run: &RunOwn<'_, W, Self>) -> Option<Coord> {
+        // The wave is stored in row-major order, so the last coordinate gives it's size
+        let (last, _) = run.wave_cell_ref_enumerate().last()?;
+        let wave_size = Size::new(last.x as u32 + 1, last.y as u32 + 1);
+
+        // Get the compatible patterns only once, a cell can have a lot of rules
+        let compatible = run
+            .wave_cell_ref_iter()
+            .map(|cell| cell.compatible_pattern_ids())
+            .collect::<Vec<_>>();
+        let compatible_at = |coord: Coord| {
+            &compatible[coord.y as usize * wave_size.width() as usize + coord.x as usize]
+        };
+
+        self.try_for_each_rule(wave_size, |rule| match rule {
+            ForbidRule::Keep(coord, pattern_id)
+                if compatible_at(coord).as_slice() != [pattern_id] =>
+            {
+                Err(coord)
+            }
+            ForbidRule::Forbid(coord, pattern_id)
+                if compatible_at(coord).binary_search(&pattern_id).is_ok() =>
+            {
+                Err(coord)
+            }
+            _ => Ok(()),
+        })
+        .err()
+    }
+}
+
+/// A custom forbid pattern of which the rules can't be checked.
+#[derive(Clone)]
+struct UncheckedForbid<F>(F);
+
+impl<F: ForbidPattern> ForbidPattern for UncheckedForbid<F> {
+    fn forbid<W: Wrap, R: Rng>(&mut self, fi: &mut ForbidInterface<W>, rng: &mut R) {
+        self.0.forbid(fi, rng);
+    }
+}
+
+impl<F: ForbidPattern + Clone + Send + Sync> CheckedForbid for UncheckedForbid<F> {
+    fn first_broken_rule<W: Wrap>(&self, _run: &RunOwn<'_, W, Self>) -> Option<Coord> {
+        None
+    }
+}
+
+/// Derive the seed of an item in a batch from the seed of the whole batch.
+///
+/// This uses the SplitMix64 mixing function, so the seeds of neighboring indices are very
+/// different.
+pub fn derive_seed(master_seed: u64, index: u64) -> u64 {
+    let mut seed =
+        master_seed.wrapping_add(index.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    seed = (seed ^ (seed >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    seed = (seed ^ (seed >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+    seed ^ (seed >> 31)
+}
+
+/// Parse an ASCII template into the width, height & tiles.
+fn parse_ascii(ascii: &str) -> Result<(u32, u32, Vec<Tile>)> {
+    let mut height = 0;
+    let mut tiles = vec![];
+
+    // Iterate over all the lines in the text
+    for (line_index, line) in ascii.lines().enumerate() {
+        // Ignore the carriage returns of Windows line endings
+        let line = line.trim_end_matches('\r');
+
+        // Skip empty lines
+        if line.is_empty() {
+            continue;
+        }
+
+        // A tab doesn't have a fixed width, so it can't be mapped to a number of tiles
+        if let Some(column) = line.chars().position(|ch| ch == '\t') {
+            return Err(anyhow!(
+                "tab character found at line {}, column {}, use spaces instead",
+                line_index + 1,
+                column + 1
+            ));
+        }
+
+        // Map the text characters to tiles in a vector
+        let line_tiles = line
+            .chars()
+            .enumerate()
+            .map(|(column, ch)| {
+                Tile::try_from_ascii(ch).with_context(|| {
+                    format!(
+                        "invalid tile at line {}, column {}",
+                        line_index + 1,
+                        column + 1
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        tiles.push(line_tiles);
+
+        height += 1;
+    }
+
+    // Get the biggest line width
+    let width = tiles
+        .iter()
+        .max_by_key(|line_tiles| line_tiles.len())
+        .ok_or_else(|| anyhow!("template is empty"))?
+        .len();
+
+    let tiles = tiles
+        .into_iter()
+        .map(|mut line_tiles| {
+            if width > line_tiles.len() {
+                // Fill the tiles with smaller widths with empty tiles
+                line_tiles.resize_with(width, || Tile::Empty);
+            }
+
+            line_tiles
+        })
+        .flatten()
+        .collect::<Vec<_>>();
+
+    Ok((width as u32, height, tiles))
+}
+
+/// Convert an array of tiles to a grid surrounded by edge tiles.
+fn padded_grid(width: u32, height: u32, map: &[Tile]) -> Result<Grid<Tile>> {
+    if map.len() != (width * height) as usize {
+        return Err(anyhow!("array doesn't match width & height"));
+    }
+
+    // The edge tile is reserved for the padding
+    if map.contains(&Tile::Edge) {
+        return Err(anyhow!("array contains an edge tile, which is reserved"));
+    }
+
+    // Only empty tiles would result in a pattern set that can't generate anything
+    if map.iter().all(|tile| tile.is_empty()) {
+        return Err(anyhow!("template has no structural tiles"));
+    }
+
+    // Add 2 to the dimensions to pad all sides with empty tiles later
+    let size = Size::new(width + 2, height + 2);
+
+    // Convert the array to a 2d grid
+    Ok(Grid::new_fn(size, |coord| {
+        if coord_is_edge(coord, size.width() as i32, size.height() as i32) {
+            // Pad the with empty tiles
+            Tile::Edge
+        } else {
+            map[(coord.y - 1) as usize * width as usize + (coord.x - 1) as usize]
+        }
+    }))
+}
+
+/// Whether there are less than `min_spacing` tiles between two positions, diagonals included.
+fn too_close(first: Coord, second: Coord, min_spacing: u32) -> bool {
+    let distance = (first.x - second.x).abs().max((first.y - second.y).abs());
+
+    distance <= min_spacing as i32
+}
+
+/// The patterns of a cell in a running collapse.
+///
+/// The wave cells only expose the chosen pattern after the run is turned into a wave, this derives
+/// it from the compatible patterns instead.
+pub(crate) trait CellPatterns {
+    /// The single pattern left in the cell, an error when there is none or more than one.
+    fn chosen_pattern_id(&self) -> Result<PatternId, ChosenPatternIdError>;
+
+    /// All patterns that are still possible in the cell, sorted by their id.
+    ///
+    /// Patterns without a weight aren't included, but all patterns of a template have one.
+    fn compatible_pattern_ids(&self) -> Vec<PatternId>;
+
+    /// The entropy of the weights of the possible patterns, this is what wfc uses to choose the
+    /// next cell to collapse.
+    fn entropy(&self) -> f32;
+}
+
+impl CellPatterns for WaveCellRef<'_> {
+    fn chosen_pattern_id(&self) -> Result<PatternId, ChosenPatternIdError> {
+        match self.enumerate_compatible_pattern_weights() {
+            EnumerateCompatiblePatternWeights::NoCompatiblePattern => {
+                Err(ChosenPatternIdError::NoCompatiblePatterns)
+            }
+            EnumerateCompatiblePatternWeights::SingleCompatiblePatternWithoutWeight(pattern_id) => {
+                Ok(pattern_id)
+            }
+            EnumerateCompatiblePatternWeights::MultipleCompatiblePatternsWithoutWeights => {
+                Err(ChosenPatternIdError::MultipleCompatiblePatterns)
+            }
+            EnumerateCompatiblePatternWeights::CompatiblePatternsWithWeights(mut patterns) => {
+                match (patterns.next(), patterns.next()) {
+                    (Some((pattern_id, _)), None) => Ok(pattern_id),
+                    _ => Err(ChosenPatternIdError::MultipleCompatiblePatterns),
+                }
+            }
+        }
+    }
+
+    fn compatible_pattern_ids(&self) -> Vec<PatternId> {
+        match self.enumerate_compatible_pattern_weights() {
+            EnumerateCompatiblePatternWeights::SingleCompatiblePatternWithoutWeight(pattern_id) => {
+                vec![pattern_id]
+            }
+            EnumerateCompatiblePatternWeights::CompatiblePatternsWithWeights(patterns) => {
+                patterns.map(|(pattern_id, _)| pattern_id).collect()
+            }
+            _ => vec![],
+        }
+    }
+
+    fn entropy(&self) -> f32 {
+        let weights = match self.enumerate_compatible_pattern_weights() {
+            EnumerateCompatiblePatternWeights::CompatiblePatternsWithWeights(patterns) => patterns
+                .map(|(_, weight)| weight as f32)
+                .filter(|weight| *weight > 0.0)
+                .collect::<Vec<_>>(),
+            _ => return 0.0,
+        };
+
+        let sum = weights.iter().sum::<f32>();
+        let sum_weight_log_weight = weights
+            .iter()
+            .map(|weight| weight * weight.log2())
+            .sum::<f32>();
+
+        sum.log2() - sum_weight_log_weight / sum
+    }
+}
+
+/// The cell of the wave that most likely caused a contradiction.
+///
+/// wfc resets the wave after a contradiction, so this can only be found from the state right
+/// before the step that failed: that step collapsed the undecided cell with the lowest entropy. The
+/// ties are broken by wfc with random noise, from those the first cell in row-major order is
+/// returned.
+fn first_contradiction<W, F>(before: &RunOwn<'_, W, F>) -> Option<Coord>
+where
+    W: Wrap,
+    F: ForbidPattern + Clone + Send + Sync,
+{
+    before
+        .wave_cell_ref_enumerate()
+        .filter(|(_, cell)| {
+            matches!(
+                cell.chosen_pattern_id(),
+                Err(ChosenPatternIdError::MultipleCompatiblePatterns)
+            )
+        })
+        .map(|(coord, cell)| (coord, cell.entropy()))
+        .fold(
+            None,
+            |lowest: Option<(Coord, f32)>, (coord, entropy)| match lowest {
+                Some((_, lowest_entropy)) if lowest_entropy <= entropy => lowest,
+                _ => Some((coord, entropy)),
+            },
+        )
+        .map(|(coord, _)| coord)
+}
+
+/// Collapse the run one step at a time, returning the state before the step that failed.
+///
+/// This clones the whole wave for every step, so it's only used when the contradiction is needed.
+fn collapse_tracked<'a, W, F, R>(
+    run: &mut RunOwn<'a, W, F>,
+    rng: &mut R,
+) -> Result<(), Box<RunOwn<'a, W, F>>>
+where
+    W: Wrap,
+    F: ForbidPattern + Clone + Send + Sync,
+    R: Rng,
+{
+    loop {
+        let before = run.clone();
+
+        match run.step(rng) {
+            Ok(Observe::Complete) => return Ok(()),
+            Ok(Observe::Incomplete) => (),
+            Err(_) => return Err(Box::new(before)),
+        }
+    }
+}
+
+/// Collapse a new run, failing when the rules of the forbid pattern aren't applied to it.
+///
+/// The error holds the cell of the wave that most likely caused the contradiction. Finding it is
+/// slow, so it's only done when `track` is set, a rule that's broken is always returned.
+fn collapse_checked<W, F, R>(
+    run: &mut RunOwn<'_, W, F>,
+    forbid: &F,
+    track: bool,
+    rng: &mut R,
+) -> Result<(), Option<Coord>>
+where
+    W: Wrap,
+    F: CheckedForbid,
+    R: Rng,
+{
+    if let Some(coord) = forbid.first_broken_rule(run) {
+        return Err(Some(coord));
+    }
+
+    if track {
+        collapse_tracked(run, rng).map_err(|before| first_contradiction(&before))
+    } else {
+        run.collapse(rng).map_err(|_| None)
+    }
+}
+
+fn coord_is_edge(coord: Coord, width: i32, height: i32) -> bool {
+    coord.x == 0 || coord.x == width - 1 || coord.y == 0 || coord.y == height - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use anyhow::Result;
+
+    /// Construct an engine directly from ASCII without generating it.
+    fn engine_from_ascii(ascii: &str) -> Engine {
+        let rows = ascii.lines().collect::<Vec<_>>();
+
+        Engine {
+            width: rows[0].chars().count() as u32,
+            height: rows.len() as u32,
+            tiles: rows
+                .iter()
+                .flat_map(|row| row.chars().map(Tile::from_ascii))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn default_test() -> Result<()> {
+        let result = Generator::default().generate_skeleton(10, 10, 100, &mut rand::thread_rng());
+        assert!(result.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn seed_test() -> Result<()> {
+        let generator = Generator::default();
+
+        let (engine, seed) = generator
+            .generate_skeleton_with_seed(10, 10, 100, &mut rand::thread_rng())
+            .unwrap();
+        let reproduced = generator
+            .generate_skeleton_from_seed(10, 10, 100, seed)
+            .unwrap();
+        assert_eq!(engine.tiles(), reproduced.tiles());
+
+        Ok(())
+    }
+
+    #[test]
+    fn deterministic_seed_test() {
+        // Use separate generators so the pattern ids are also created twice
+        let generator = Generator::default();
+        let other = Generator::default();
+        assert_eq!(generator.pattern_tiles(), other.pattern_tiles());
+
+        let engine = generator
+            .generate_skeleton_from_seed(12, 8, 100, 42)
+            .unwrap();
+        let reproduced = other.generate_skeleton_from_seed(12, 8, 100, 42).unwrap();
+        assert_eq!(engine.to_ascii(), reproduced.to_ascii());
+    }
+
+    #[test]
+    fn derive_seed_test() {
+        assert_eq!(derive_seed(42, 5), derive_seed(42, 5));
+        assert_ne!(derive_seed(42, 5), derive_seed(42, 6));
+        assert_ne!(derive_seed(42, 5), derive_seed(43, 5));
+
+        // All seeds of a batch are unique
+        let mut seeds = (0..1000)
+            .map(|index| derive_seed(0, index))
+            .collect::<Vec<_>>();
+        seeds.sort_unstable();
+        seeds.dedup();
+        assert_eq!(seeds.len(), 1000);
+    }
+
+    #[test]
+    fn generate_batch_test() {
+        let generator = Generator::default();
+
+        let batch = generator.generate_batch(10, 10, 100, 42, 3);
+        assert_eq!(batch.len(), 3);
+
+        let reproduced = generator
+            .generate_skeleton_from_seed(10, 10, 100, derive_seed(42, 2))
+            .unwrap();
+        assert_eq!(batch[2].as_ref().unwrap().tiles(), reproduced.tiles());
+    }
+
+    #[test]
+    fn forbidding_test() {
+        struct AlwaysWall;
+
+        impl AnyResolver for AlwaysWall {
+            fn resolve(&self, _ctx: &TileContext) -> Tile {
+                Tile::Wall
+            }
+        }
+
+        // The any tiles can still become crosses, so only keep the crosses from the template
+        let generator = Generator::default().with_any_resolver(AlwaysWall);
+
+        let engine = generator
+            .generate_skeleton_forbidding(10, 10, &[Tile::Cross], 100, &mut rand::thread_rng())
+            .unwrap();
+        assert!(!engine.tiles().contains(&Tile::Cross));
+
+        // The default template can't stand without wheels
+        assert!(generator
+            .generate_skeleton_forbidding(10, 10, &[Tile::Wheel], 10, &mut rand::thread_rng())
+            .is_none());
+    }
+
+    #[test]
+    fn best_effort_test() -> Result<()> {
+        // A single fixed box can only fill an output of the same size
+        let generator = Generator::from_ascii("+-+\n|.|\n+-+")?;
+
+        let (engine, (width, height)) = generator
+            .generate_skeleton_best_effort(10, 10, 10, &mut rand::thread_rng())
+            .unwrap();
+        assert!(width < 10 && height < 10);
+        assert_eq!((engine.width(), engine.height()), (width, height));
+
+        Ok(())
+    }
+
+    #[test]
+    fn letterboxed_test() {
+        let generator = Generator::default();
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let (engine, offset) = generator
+            .generate_skeleton_letterboxed(8, 6, Size::new(16, 9), 10, &mut rng)
+            .unwrap();
+        assert_eq!((engine.width(), engine.height()), (16, 9));
+        // Both sides are padded with the same amount of columns
+        assert_eq!(offset, Coord::new(4, 1));
+        assert_eq!(offset.x as u32, engine.width() - 8 - offset.x as u32);
+
+        // Everything outside of the skeleton is empty
+        let grid = engine.to_grid();
+        assert!(grid.enumerate().all(|(coord, tile)| {
+            let inside = (4..12).contains(&coord.x) && (1..7).contains(&coord.y);
+
+            inside || *tile == Tile::Empty
+        }));
+
+        assert!(generator
+            .generate_skeleton_letterboxed(20, 6, Size::new(16, 9), 10, &mut rng)
+            .is_none());
+    }
+
+    #[test]
+    fn custom_forbid_test() {
+        #[derive(Clone)]
+        struct NoForbid;
+
+        impl ForbidPattern for NoForbid {
+            fn forbid<W: Wrap, R: Rng>(&mut self, _fi: &mut ForbidInterface<W>, _rng: &mut R) {}
+        }
+
+        let generator = Generator::default();
+
+        let engine = generator
+            .generate_skeleton_with_forbid(10, 10, 100, NoForbid, &mut rand::thread_rng())
+            .unwrap();
+        assert_eq!((engine.width(), engine.height()), (10, 10));
+    }
+
+    #[test]
+    fn contradiction_report_test() -> Result<()> {
+        // A single fixed box can never fill a bigger output
+        let generator = Generator::from_ascii("+-+\n|.|\n+-+")?;
+
+        let report = generator.generate_report(10, 10, &mut rand::thread_rng());
+        assert!(report.engine().is_none());
+        assert!(!report.contradictions().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn center_of_mass_test() {
+        let engine = engine_from_ascii("+--+\n|  |\no--o");
+
+        let (x, y) = engine.center_of_mass(|_| 1.0);
+        assert!((x - 2.0).abs() < f32::EPSILON);
+        assert!((y - 1.5).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn graph_test() {
+        let engine = engine_from_ascii("+--+\n|..|\n|..|\n+--+");
+
+        let graph = engine.to_graph();
+        assert_eq!(graph.nodes().len(), 4);
+        assert!(graph
+            .nodes()
+            .iter()
+            .all(|node| node.kind == NodeKind::Cross));
+        assert_eq!(graph.edges().len(), 4);
+        assert!(graph.to_dot().starts_with("graph engine {"));
+    }
+
+    #[test]
+    fn timeout_test() -> Result<()> {
+        let generator = Generator::from_ascii("+-+\n|.|\n+-+")?;
+
+        let result = generator.generate_skeleton_timeout(
+            10,
+            10,
+            Duration::from_millis(1),
+            &mut rand::thread_rng(),
+        );
+        assert!(result.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn logging_test() {
+        use log::{LevelFilter, Log, Metadata, Record};
+        use std::{
+            sync::Mutex,
+            thread::{self, ThreadId},
+        };
+
+        struct CaptureLogger {
+            thread: ThreadId,
+            messages: Arc<Mutex<Vec<String>>>,
+        }
+
+        impl Log for CaptureLogger {
+            fn enabled(&self, metadata: &Metadata) -> bool {
+                metadata.target().starts_with("siege")
+            }
+
+            fn log(&self, record: &Record) {
+                // The logger is global, so ignore the other tests running at the same time
+                if self.enabled(record.metadata()) && thread::current().id() == self.thread {
+                    self.messages
+                        .lock()
+                        .unwrap()
+                        .push(record.args().to_string());
+                }
+            }
+
+            fn flush(&self) {}
+        }
+
+        let messages = Arc::new(Mutex::new(vec![]));
+        log::set_logger(Box::leak(Box::new(CaptureLogger {
+            thread: thread::current().id(),
+            messages: messages.clone(),
+        })))
+        .unwrap();
+        log::set_max_level(LevelFilter::Debug);
+
+        let engine = Generator::default().generate_skeleton(13, 7, 100, &mut rand::thread_rng());
+        assert!(engine.is_some());
+
+        let messages = messages.lock().unwrap();
+        assert!(messages.iter().any(|message| message.contains("patterns")));
+        assert!(messages
+            .iter()
+            .any(|message| message.starts_with("collapsed a 15x9 wave")));
+    }
+
+    #[test]
+    fn constraints_test() {
+        let engine = Generator::default()
+            .generate_skeleton_with_constraints(
+                10,
+                10,
+                100,
+                &mut rand::thread_rng(),
+                &[(0, 9, Tile::Wheel)],
+            )
+            .unwrap();
+        assert_eq!(engine.tiles()[9 * 10], Tile::Wheel);
+    }
+
+    #[test]
+    fn write_ascii_test() -> Result<()> {
+        let engine = engine_from_ascii("+--+\n|..|\no--o");
+
+        let mut ascii = vec![];
+        engine.write_ascii(&mut ascii)?;
+        assert_eq!(String::from_utf8(ascii)?, engine.to_ascii());
+        assert_eq!(engine.to_ascii(), "+--+\n|..|\no--o");
+
+        let rows = engine.ascii_rows().collect::<Vec<_>>();
+        assert_eq!(rows.len(), engine.height() as usize);
+        assert!(rows
+            .iter()
+            .all(|row| row.chars().count() == engine.width() as usize));
+        assert_eq!(rows[1], "|..|");
+
+        Ok(())
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn from_image_test() {
+        let black = [0, 0, 0];
+        let white = [0xFF, 0xFF, 0xFF];
+        let palette = [(white, Tile::Empty), (black, Tile::Wall)]
+            .iter()
+            .copied()
+            .collect::<HashMap<_, _>>();
+
+        let image = image::RgbImage::from_fn(4, 4, |x, _| {
+            image::Rgb(if x % 2 == 0 { black } else { white })
+        });
+        let generator = Generator::from_rgb_image(&image, &palette).unwrap();
+        assert_eq!(generator.grid.width(), 4 + 2);
+
+        let image = image::RgbImage::from_fn(4, 4, |x, y| {
+            image::Rgb(if x == 2 && y == 3 {
+                [0xFF, 0, 0]
+            } else {
+                black
+            })
+        });
+        let err = Generator::from_rgb_image(&image, &palette).err().unwrap();
+        assert!(err.to_string().contains("(2, 3)"));
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn to_image_test() {
+        let engine = engine_from_ascii("+--+\n|..|\no--o");
+
+        let image = engine.to_image(8);
+        assert_eq!(image.dimensions(), (4 * 8, 3 * 8));
+
+        // The four wheels are merged into a single big wheel
+        let engine = engine_from_ascii("oo \noo ");
+        let image = engine.to_image(8);
+        let wheel = TileTheme::default().style(Tile::Wheel).solid();
+        assert_eq!(image.get_pixel(8, 8).0, wheel);
+        assert_eq!(image.get_pixel(0, 0).0, TRANSPARENT);
+        assert_eq!(image.get_pixel(20, 8).0, TRANSPARENT);
+    }
+
+    #[test]
+    fn clone_test() {
+        let generator = Generator::default();
+        let cloned = generator.clone();
+
+        assert!(generator
+            .generate_skeleton(10, 10, 100, &mut rand::thread_rng())
+            .is_some());
+        assert!(cloned
+            .generate_skeleton(10, 10, 100, &mut rand::thread_rng())
+            .is_some());
+    }
+
+    #[test]
+    fn can_generate_test() {
+        let generator = Generator::default();
+
+        assert!(generator.can_generate(10, 10, 100, &mut rand::thread_rng()));
+        assert!(!generator.can_generate(1, 1, 10, &mut rand::thread_rng()));
+    }
+
+    #[test]
+    fn unattached_wheels_test() {
+        let engine = engine_from_ascii("o   \n    \n+--o");
+
+        assert_eq!(engine.unattached_wheels(), vec![Coord::new(0, 0)]);
+    }
+
+    #[test]
+    fn adjacencies_test() {
+        let adjacencies = Generator::default().adjacencies();
+
+        assert!(!adjacencies.is_empty());
+        assert!(adjacencies
+            .iter()
+            .all(|(_, _, compatible)| !compatible.is_empty()));
+    }
+
+    #[test]
+    fn min_wheels_test() {
+        let engine = Generator::default()
+            .generate_skeleton_with_min_wheels(10, 10, 2, 100, &mut rand::thread_rng())
+            .unwrap();
+
+        let wheels = engine
+            .tiles()
+            .iter()
+            .filter(|tile| **tile == Tile::Wheel)
+            .count();
+        assert!(wheels >= 2);
+    }
+
+    #[test]
+    fn wheels_at_bottom_test() {
+        let engine = Generator::default()
+            .with_wheels_at_bottom(0.6)
+            .generate_skeleton(10, 10, 100, &mut rand::thread_rng())
+            .unwrap();
+
+        assert!(engine.tiles()[..6 * 10]
+            .iter()
+            .all(|tile| *tile != Tile::Wheel));
+    }
+
+    #[test]
+    fn unicode_test() -> Result<()> {
+        let tiles = [
+            Tile::Empty,
+            Tile::Any,
+            Tile::Wheel,
+            Tile::HorizontalBeam,
+            Tile::VerticalBeam,
+            Tile::DiagonalBeam1,
+            Tile::DiagonalBeam2,
+            Tile::Cross,
+            Tile::Wall,
+            Tile::Joint,
+        ];
+        for tile in tiles.iter() {
+            assert_eq!(Tile::from_ascii(tile.to_unicode()), *tile);
+        }
+
+        let engine = engine_from_ascii("┼──┼\n│╱╲│\no──o");
+        assert_eq!(engine.to_ascii(), "+--+\n|/\\|\no--o");
+        assert_eq!(engine.to_unicode(), "┼──┼\n│╱╲│\no──o");
+
+        // Templates can also be written with the box-drawing characters
+        Generator::from_ascii("┼──┼\n│..│\no──o")?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn density_test() {
+        let engine = engine_from_ascii("....\n....");
+        assert!((engine.density() - 1.0).abs() < f32::EPSILON);
+        assert!((engine.aspect_ratio() - 2.0).abs() < f32::EPSILON);
+
+        let engine = engine_from_ascii("....\n    ");
+        assert!((engine.density() - 0.5).abs() < f32::EPSILON);
+
+        assert_eq!(Engine::default().aspect_ratio(), 0.0);
+    }
+
+    #[test]
+    fn presets_test() {
+        // The presets have very different shapes, this size fits all of them
+        for preset in Preset::ALL.iter() {
+            let result =
+                Generator::preset(*preset).generate_skeleton(12, 7, 100, &mut rand::thread_rng());
+            assert!(result.is_some(), "preset {:?} failed", preset);
+        }
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn discriminants_test() -> Result<()> {
+        let engine = engine_from_ascii("+--+\n|..|\no--o");
+
+        let discriminants = engine.to_discriminants();
+        let converted = Engine::from_discriminants(4, 3, &discriminants)?;
+        assert_eq!(converted.tiles(), engine.tiles());
+
+        assert!(Engine::from_discriminants(1, 1, &[Tile::Edge as u8]).is_err());
+        // The amount of tiles doesn't fit in 32 bits
+        assert!(Engine::from_discriminants(u32::MAX, 2, &discriminants).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn footprint_test() {
+        let engine = engine_from_ascii("  +----+  \n  o    o  \n          ");
+        assert_eq!(engine.footprint(), Some((2, 7)));
+
+        let engine = engine_from_ascii("   \n   ");
+        assert_eq!(engine.footprint(), None);
+    }
+
+    #[test]
+    fn regenerate_region_test() {
+        let generator = Generator::default();
+        let engine = generator
+            .generate_skeleton(10, 10, 100, &mut rand::thread_rng())
+            .unwrap();
+
+        let regenerated = generator
+            .regenerate_region(&engine, (3, 3, 4, 4), 100, &mut rand::thread_rng())
+            .unwrap();
+        for (index, (old, new)) in engine.tiles().iter().zip(regenerated.tiles()).enumerate() {
+            let (x, y) = (index % 10, index / 10);
+            if !(3..7).contains(&x) || !(3..7).contains(&y) {
+                assert_eq!(old, new);
+            }
+        }
+
+        // Regions that don't fit, including ones of which the end overflows
+        for region in [(8, 0, 4, 1), (u32::MAX, 0, 2, 1), (0, u32::MAX, 1, 2)].iter() {
+            assert!(generator
+                .regenerate_region(&engine, *region, 100, &mut rand::thread_rng())
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn diagonal_direction_test() {
+        assert_eq!(Tile::diagonal_slash().to_ascii(), '/');
+        assert_eq!(Tile::diagonal_backslash().to_ascii(), '\\');
+
+        assert_eq!(
+            Tile::diagonal_slash().diagonal_direction(),
+            Some(DiagDir::Forward)
+        );
+        assert_eq!(
+            Tile::diagonal_backslash().diagonal_direction(),
+            Some(DiagDir::Back)
+        );
+        assert_eq!(Tile::Cross.diagonal_direction(), None);
+
+        assert_eq!(DiagDir::Forward.to_tile(), Tile::diagonal_slash());
+        assert_eq!(DiagDir::Back.to_tile(), Tile::diagonal_backslash());
+    }
+
+    #[test]
+    fn raw_test() {
+        // The default template mostly consists of any tiles
+        let mut engine = Generator::default()
+            .generate_skeleton_raw(10, 10, 100, &mut rand::thread_rng())
+            .unwrap();
+        assert!(engine.tiles().contains(&Tile::Any));
+
+        engine.resolve_any();
+        assert!(!engine.tiles().contains(&Tile::Any));
+    }
+
+    #[test]
+    fn any_resolver_test() {
+        struct AlwaysWall;
+
+        impl AnyResolver for AlwaysWall {
+            fn resolve(&self, _ctx: &TileContext) -> Tile {
+                Tile::Wall
+            }
+        }
+
+        let mut engine = engine_from_ascii("+*+\n*o*\n+-+");
+        engine.resolve_any_with(&AlwaysWall);
+        assert_eq!(engine.to_ascii(), "+.+\n.o.\n+-+");
+
+        // The default resolver is the same as resolving without one
+        let mut engine = engine_from_ascii("+*+\n| |\n+*+");
+        let mut default = engine.clone();
+        engine.resolve_any();
+        default.resolve_any_with(&DefaultAnyResolver);
+        assert_eq!(engine.tiles(), default.tiles());
+        assert_eq!(engine.to_ascii(), "+-+\n| |\n+-+");
+
+        // Generators pass their resolver to every generated engine
+        let generator = Generator::default().with_any_resolver(AlwaysWall);
+        assert_eq!(
+            generator
+                .clone()
+                .any_resolver
+                .resolve(&TileContext::default()),
+            Tile::Wall
+        );
+
+        // The same seed collapses the same way, so the any tiles of the raw engine become walls
+        let engine = generator
+            .generate_skeleton(12, 8, 100, &mut StdRng::seed_from_u64(0))
+            .unwrap();
+        let raw = generator
+            .generate_skeleton_raw(12, 8, 100, &mut StdRng::seed_from_u64(0))
+            .unwrap();
+        assert!(raw.tiles().contains(&Tile::Any));
+        assert!(!engine.tiles().contains(&Tile::Any));
+        for (tile, raw_tile) in engine.tiles().iter().zip(raw.tiles()) {
+            if *raw_tile == Tile::Any {
+                assert_eq!(*tile, Tile::Wall);
+            } else {
+                assert_eq!(tile, raw_tile);
+            }
+        }
+    }
+
+    #[test]
+    fn from_grid_test() -> Result<()> {
+        let grid = Grid::new_fn(Size::new(3, 2), |coord| {
+            if coord.y == 0 {
+                Tile::HorizontalBeam
+            } else {
+                Tile::Wheel
+            }
+        });
+
+        let engine = Engine::from_grid(&grid)?;
+        assert_eq!(engine.to_ascii(), "---\nooo");
+
+        let grid = Grid::new_fn(Size::new(1, 1), |_| Tile::Edge);
+        assert!(Engine::from_grid(&grid).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_ascii_multi_test() -> Result<()> {
+        let generator = Generator::from_ascii_multi(&[
+            include_str!("default.ascii"),
+            include_str!("tower.ascii"),
+        ])?;
+
+        assert!(generator
+            .generate_skeleton(10, 10, 100, &mut rand::thread_rng())
+            .is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn crlf_test() -> Result<()> {
+        let lf = Generator::from_ascii("+--+\n|..|\no--o\n")?;
+        let crlf = Generator::from_ascii("+--+\r\n|..|\r\no--o\r\n")?;
+        assert_eq!(
+            lf.grid.iter().collect::<Vec<_>>(),
+            crlf.grid.iter().collect::<Vec<_>>()
+        );
+
+        assert!(Generator::from_ascii("+--+\n|\t.|\no--o").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn pattern_ids_test() {
+        let (engine, pattern_ids) = Generator::default()
+            .generate_skeleton_with_pattern_ids(10, 8, 100, &mut rand::thread_rng())
+            .unwrap();
+
+        assert_eq!(engine.tiles().len(), 10 * 8);
+        assert_eq!(pattern_ids.len(), 12 * 10);
+    }
+
+    #[test]
+    fn wall_rects_test() {
+        let engine = Engine {
+            width: 50,
+            height: 50,
+            tiles: vec![Tile::Wall; 50 * 50],
+        };
+        assert_eq!(engine.wall_rects(), vec![(0, 0, 50, 50)]);
+
+        let engine = engine_from_ascii("+--+\n|..|\n|..|\n+--+");
+        assert_eq!(engine.wall_rects(), vec![(1, 1, 2, 2)]);
+    }
+
+    #[test]
+    fn symmetry_score_test() {
+        let engine = engine_from_ascii("/--\\\n|oo|\n\\--/");
+        assert!((engine.symmetry_score(Axis::Vertical) - 1.0).abs() < f32::EPSILON);
+        assert!((engine.symmetry_score(Axis::Horizontal) - 1.0).abs() < f32::EPSILON);
+
+        let engine = engine_from_ascii("/...\n....");
+        assert!(engine.symmetry_score(Axis::Vertical) < 1.0);
+        assert!(engine.symmetry_score(Axis::Horizontal) < 1.0);
+    }
+
+    #[test]
+    fn complexity_score_test() {
+        let weights = ComplexityWeights::default();
+
+        let block = engine_from_ascii("...\n...");
+        assert!((block.complexity_score(weights) - 1.0).abs() < f32::EPSILON);
+
+        // 5 tile types, 7 direction changes & 2 wheels
+        let varied = engine_from_ascii("+-+\n|/|\no o");
+        assert!((varied.complexity_score(weights) - 12.5).abs() < f32::EPSILON);
+        assert!(varied.complexity_score(weights) > block.complexity_score(weights));
+
+        let only_wheels = ComplexityWeights {
+            tile_types: 0.0,
+            direction_changes: 0.0,
+            wheels: 1.0,
+        };
+        assert!((varied.complexity_score(only_wheels) - 2.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn min_fill_ratio_test() {
+        let engine = engine_from_ascii("    \n .. \n .  \n    ");
+        assert!((engine.fill_ratio() - 0.25).abs() < f32::EPSILON);
+        assert!(engine_from_ascii("    ").fill_ratio().abs() < f32::EPSILON);
+
+        // Either a big enough engine is found or it fails without panicking
+        if let Some(engine) = Generator::default()
+            .with_min_fill_ratio(0.9)
+            .generate_skeleton(20, 10, 100, &mut rand::thread_rng())
+        {
+            assert!(engine.fill_ratio() >= 0.9);
+        }
+    }
+
+    #[test]
+    fn theme_test() {
+        let engine = engine_from_ascii("o-o\n...");
+        let theme = TileTheme::default().with_style(
+            Tile::Wall,
+            TileStyle {
+                fill: [0x12, 0x34, 0x56, 0xFF],
+                stroke: TRANSPARENT,
+                stroke_width: 0.0,
+            },
+        );
+
+        assert_ne!(engine.to_svg(1.0), engine.to_svg_with_theme(1.0, &theme));
+        assert_eq!(TileTheme::empty().style(Tile::Wall), TileStyle::INVISIBLE);
+    }
+
+    #[test]
+    fn steps_test() {
+        let generator = Generator::default();
+        let mut rng = rand::thread_rng();
+
+        // Retry until a collapse without a contradiction happens
+        let (steps, last) = (0..100)
+            .find_map(|_| {
+                let mut iter = generator.steps(10, 10, &mut rng);
+                let mut steps = iter.by_ref().collect::<Vec<_>>();
+
+                if iter.contradiction() {
+                    None
+                } else {
+                    Some((steps.len(), steps.pop().unwrap()))
+                }
+            })
+            .unwrap();
+
+        assert!(steps > 0 && steps <= 10 * 10);
+        assert!(last.tiles().iter().all(|tile| *tile != Tile::Any));
+    }
+
+    #[test]
+    fn crop_test() -> Result<()> {
+        let engine = engine_from_ascii("+--+\n|..|\no--o");
+
+        let cropped = engine.crop(1, 1, 3, 2)?;
+        assert_eq!(cropped.to_ascii(), "..|\n--o");
+
+        assert!(engine.crop(2, 0, 3, 1).is_err());
+        assert!(engine.crop(0, 3, 1, 1).is_err());
+        assert!(engine.crop(0, 0, 0, 1).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn can_bear_weight_test() {
+        let tower = engine_from_ascii("|\n|\n|\no");
+        assert!(!tower.can_bear_weight(|_| Material::Rope));
+        assert!(tower.can_bear_weight(|_| Material::Wood));
+
+        let frame = engine_from_ascii("+--+\n|..|\n|..|\no--o");
+        assert!(frame.can_bear_weight(|_| Material::Metal));
+
+        // Nothing carries the frame to the ground
+        let floating = engine_from_ascii("+-+\n   \n   ");
+        assert!(!floating.can_bear_weight(|_| Material::Metal));
+
+        // The arm is carried sideways by the tower, which then carries it's weight as well
+        let arm = engine_from_ascii("+--\n|  \n|  \no  ");
+        assert!(arm.can_bear_weight(|_| Material::Metal));
+        assert!(!arm.can_bear_weight(|tile| match tile {
+            Tile::Wheel => Material::Wood,
+            _ => Material::Metal,
+        }));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn generate_skeleton_async_test() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Generator>();
+
+        let future =
+            Generator::default().generate_skeleton_async(10, 10, 100, StdRng::seed_from_u64(0));
+        assert!(futures::executor::block_on(future).is_some());
+    }
+
+    #[test]
+    fn bytes_test() -> Result<()> {
+        let engine = engine_from_ascii("+--+\n|..|\no--o");
+
+        let bytes = engine.to_bytes();
+        let converted = Engine::from_bytes(&bytes)?;
+        assert_eq!(converted.width(), 4);
+        assert_eq!(converted.height(), 3);
+        assert_eq!(converted.tiles(), engine.tiles());
+
+        // Wrong magic
+        let mut wrong = bytes.clone();
+        wrong[0] = 0;
+        assert!(Engine::from_bytes(&wrong).is_err());
+        // Missing a tile
+        assert!(Engine::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        // Header only
+        assert!(Engine::from_bytes(&bytes[..4]).is_err());
+        // Edge tile
+        let mut wrong = bytes;
+        wrong[12] = Tile::Edge as u8;
+        assert!(Engine::from_bytes(&wrong).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn generate_skeleton_into_test() {
+        let generator = Generator::default();
+        let mut rng = rand::thread_rng();
+        let mut engine = Engine::default();
+
+        assert!(generator.generate_skeleton_into(&mut engine, 10, 10, 100, &mut rng));
+        assert_eq!(engine.tiles().len(), 10 * 10);
+        let capacity = engine.tiles.capacity();
+
+        assert!(generator.generate_skeleton_into(&mut engine, 10, 10, 100, &mut rng));
+        assert_eq!(engine.tiles().len(), 10 * 10);
+        assert_eq!(engine.tiles.capacity(), capacity);
+        assert!(engine.tiles().iter().all(|tile| *tile != Tile::Any));
+    }
+
+    #[test]
+    fn open_sides_test() {
+        let generator = Generator::default();
+        let mut rng = rand::thread_rng();
+        let open_sides = BorderSides {
+            right: true,
+            ..BorderSides::default()
+        };
+
+        let touches_right = (0..10).any(|_| {
+            let engine = generator
+                .generate_skeleton_with_open_sides(10, 10, open_sides, 100, &mut rng)
+                .unwrap();
+            assert_eq!(engine.tiles().len(), 10 * 10);
+
+            assert!(engine.tiles().iter().all(|tile| *tile != Tile::Edge));
+
+            engine.tiles().chunks(10).any(|row| row[9] != Tile::Empty)
+        });
+        assert!(touches_right);
+    }
+
+    #[test]
+    fn as_u8_test() {
+        let tiles = [
+            Tile::Empty,
+            Tile::Any,
+            Tile::Wheel,
+            Tile::HorizontalBeam,
+            Tile::VerticalBeam,
+            Tile::DiagonalBeam1,
+            Tile::DiagonalBeam2,
+            Tile::Cross,
+            Tile::Wall,
+            Tile::Joint,
+            Tile::Edge,
+        ];
+        for tile in tiles.iter() {
+            assert_eq!(Tile::from_u8(tile.as_u8()), Some(*tile));
+        }
+
+        assert_eq!(Tile::Edge.as_u8(), 255);
+        assert_eq!("2".parse::<Tile>().unwrap().as_u8(), 2);
+    }
+
+    #[test]
+    fn wheels_test() {
+        let engine = engine_from_ascii("+--+\n|..|\no--o");
+
+        assert_eq!(
+            engine.wheels(),
+            vec![
+                WheelInfo {
+                    center: (0.5, 2.5),
+                    radius: 0.5
+                },
+                WheelInfo {
+                    center: (3.5, 2.5),
+                    radius: 0.5
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn merged_wheels_test() {
+        let engine = engine_from_ascii("+--+\n|..|\n+oo+");
+        assert_eq!(
+            engine.wheels(),
+            vec![WheelInfo {
+                center: (2.0, 2.5),
+                radius: 1.0
+            }]
+        );
+
+        // Diagonal wheels aren't merged, a 2x2 block is
+        let engine = engine_from_ascii("o  oo\n o oo");
+        assert_eq!(engine.wheels().len(), 3);
+        assert_eq!(
+            engine.wheels()[1],
+            WheelInfo {
+                center: (4.0, 1.0),
+                radius: 1.0
+            }
+        );
+        assert!(engine.wheels()[1].contains(3.1, 1.0));
+        assert!(!engine.wheels()[1].contains(3.1, 0.1));
+
+        // A single merged wheel is drawn
+        struct CircleCanvas(Vec<((f32, f32), f32)>);
+
+        impl Canvas for CircleCanvas {
+            fn fill_rect(&mut self, _tile: Tile, _x: f32, _y: f32, _width: f32, _height: f32) {}
+
+            fn draw_line(&mut self, _tile: Tile, _start: (f32, f32), _end: (f32, f32), _: f32) {}
+
+            fn draw_circle(&mut self, _tile: Tile, center: (f32, f32), radius: f32) {
+                self.0.push((center, radius));
+            }
+        }
+
+        let mut canvas = CircleCanvas(vec![]);
+        engine_from_ascii("oo").render(&mut canvas, 10.0);
+        assert_eq!(canvas.0, vec![((10.0, 5.0), 10.0)]);
+    }
+
+    #[test]
+    fn recovery_test() -> Result<()> {
+        // The windows of the tower make a lot of collapses run into a contradiction
+        let generator = Generator::from_ascii(
+            "  *****      \n  *****      \n  *****      \n**********   \n**********   \n\
+             **+---+***---\n**|   |***   \n**|   |***   \n**|   |***   \n**+---+***   \n\
+             **********   ",
+        )?;
+        let total_attempts = |strategy| {
+            let mut rng = StdRng::seed_from_u64(0);
+
+            (0..10)
+                .map(|_| {
+                    let (engine, attempts) = generator
+                        .generate_skeleton_with_recovery(20, 14, 100, strategy, &mut rng)
+                        .unwrap();
+                    assert_eq!(engine.tiles().len(), 20 * 14);
+
+                    attempts
+                })
+                .sum::<usize>()
+        };
+
+        let full_restart = total_attempts(RecoveryStrategy::FullRestart);
+        assert!(full_restart > 10);
+        // A window covering the whole wave keeps nothing, which is the same as a full restart
+        assert_eq!(
+            full_restart,
+            total_attempts(RecoveryStrategy::LocalRetry { radius: 32 })
+        );
+        // Keeping the cells away from the contradictions saves attempts
+        assert!(total_attempts(RecoveryStrategy::LocalRetry { radius: 2 }) < full_restart);
+
+        Ok(())
+    }
+
+    #[test]
+    fn big_to_grid_test() {
+        let (width, height) = (1000, 600);
+        let tiles = (0..width * height)
+            .map(|index| match index % 7 {
+                0 => Tile::Wall,
+                1 => Tile::Cross,
+                2 => Tile::Wheel,
+                _ => Tile::Empty,
+            })
+            .collect::<Vec<_>>();
+        let engine = Engine {
+            width,
+            height,
+            tiles,
+        };
+
+        let grid = engine.to_grid();
+        assert_eq!(grid.width(), width);
+        assert_eq!(grid.height(), height);
+        assert!(grid.iter().eq(engine.tiles().iter()));
+        assert_eq!(
+            *grid.get_checked(Coord::new(999, 599)),
+            engine.tiles()[engine.tiles().len() - 1]
+        );
+    }
+
+    #[test]
+    fn index_test() {
+        let engine = engine_from_ascii("+--+\n|..|\no--o");
+
+        assert_eq!(engine.index(0, 0), Some(0));
+        assert_eq!(engine.index(3, 0), Some(3));
+        assert_eq!(engine.index(0, 2), Some(8));
+        assert_eq!(engine.index(3, 2), Some(11));
+        assert_eq!(engine.index(4, 0), None);
+        assert_eq!(engine.index(0, 3), None);
+        assert_eq!(engine.index(u32::MAX, u32::MAX), None);
+    }
+
+    #[test]
+    fn tileable_test() -> Result<()> {
+        let mut rng = rand::thread_rng();
+
+        // The empty & any tiles of a template can also wrap around
+        let engine = Generator::default()
+            .generate_tileable_skeleton(12, 8, 100, &mut rng)
+            .unwrap();
+        assert_eq!(engine.tiles().len(), 12 * 8);
+        assert!(engine
+            .tiles()
+            .iter()
+            .all(|tile| *tile != Tile::Edge && *tile != Tile::Any));
+
+        // The template only repeats horizontally, so the height can't differ
+        let generator = Generator::from_ascii("+--+--+\n|..|..|\n|..|..|\no--o--o")?;
+        let engine = generator
+            .generate_tileable_skeleton(9, 4, 100, &mut rng)
+            .unwrap();
+
+        // Placed side by side the tiles at the seam must also be next to each other in the
+        // template
+        let template = generator.grid.clone();
+        let template_pairs = template
+            .enumerate()
+            .filter(|(coord, _)| coord.x > 0)
+            .map(|(coord, tile)| {
+                (
+                    *template.get_checked(Coord::new(coord.x - 1, coord.y)),
+                    *tile,
+                )
+            })
+            .collect::<Vec<_>>();
+        for row in engine.tiles().chunks(9) {
+            assert!(template_pairs.contains(&(row[8], row[0])));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn decorate_test() {
+        let ascii = "+-+-+-+\n|.|.|.|\n+-+-+-+\n|.|.|.|\n+-+-+-+";
+
+        let mut first = engine_from_ascii(ascii);
+        first.decorate(&mut StdRng::seed_from_u64(0));
+        let mut second = engine_from_ascii(ascii);
+        second.decorate(&mut StdRng::seed_from_u64(0));
+        assert_eq!(first.tiles(), second.tiles());
+
+        // Only crosses connected on three or more sides can become joints
+        let original = engine_from_ascii(ascii);
+        for (tile, original) in first.tiles().iter().zip(original.tiles().iter()) {
+            assert!(tile == original || (*tile == Tile::Joint && *original == Tile::Cross));
+        }
+        for corner in [0, 6, 4 * 7, 4 * 7 + 6].iter() {
+            assert_eq!(first.tiles()[*corner], Tile::Cross);
+        }
+    }
+
+    #[test]
+    fn output_size_test() -> Result<()> {
+        assert!(OutputSize::try_from((0, 10)).is_err());
+        assert!(OutputSize::try_from((10, 0)).is_err());
+
+        let size = OutputSize::try_from((12, 10))?;
+        assert_eq!((size.width(), size.height()), (12, 10));
+
+        let generator = Generator::default();
+        let mut rng = rand::thread_rng();
+        assert!(generator.generate_skeleton(0, 10, 10, &mut rng).is_none());
+        assert!(generator
+            .generate_skeleton_sized(size, 100, &mut rng)
+            .is_some());
+
+        // Every way of generating bails out on an empty output instead of panicking
+        let mut engine = Engine::default();
+        assert!(!generator.generate_skeleton_into(&mut engine, 10, 0, 10, &mut rng));
+        assert!(generator
+            .generate_skeleton_timeout(0, 10, Duration::from_millis(10), &mut rng)
+            .is_none());
+        assert!(generator
+            .generate_report(10, 0, &mut rng)
+            .cells()
+            .is_empty());
+        assert!(generator
+            .generate_skeleton_raw(0, 10, 10, &mut rng)
+            .is_none());
+        assert!(generator
+            .generate_skeleton_with_edges(0, 10, 10, &mut rng)
+            .is_none());
+        assert!(generator
+            .generate_skeleton_with_pattern_ids(10, 0, 10, &mut rng)
+            .is_none());
+        assert_eq!(generator.steps(0, 10, &mut rng).count(), 0);
+        assert!(generator
+            .generate_skeleton_with_recovery(0, 10, 10, RecoveryStrategy::FullRestart, &mut rng)
+            .is_none());
+        assert!(generator
+            .generate_tileable_skeleton(0, 10, 10, &mut rng)
+            .is_none());
+        assert!(!generator.can_generate(10, 0, 10, &mut rng));
+        assert_eq!(generator.success_rate(10, 0, 10, &mut rng), 0.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn diff_test() {
+        let engine = engine_from_ascii("+--+\n|..|\no--o");
+        assert_eq!(engine.diff(&engine.clone()), Some(vec![]));
+
+        let mut changed = engine.clone();
+        changed.tiles[6] = Tile::Empty;
+        assert_eq!(
+            engine.diff(&changed),
+            Some(vec![(2, 1, Tile::Wall, Tile::Empty)])
+        );
+
+        assert_eq!(engine.diff(&engine_from_ascii("+--+\no--o")), None);
+    }
+
+    #[test]
+    fn normalize_joints_test() {
+        let mut engine = engine_from_ascii(" | \n---\n | ");
+        engine.normalize_joints();
+        assert_eq!(engine.to_ascii(), " | \n-+-\n | ");
+
+        // Parallel beams are left alone
+        let mut engine = engine_from_ascii("---\n---");
+        engine.normalize_joints();
+        assert_eq!(engine.to_ascii(), "---\n---");
+    }
+
+    #[test]
+    fn stroke_width_test() {
+        let engine = engine_from_ascii("o-o");
+        let beam = TileTheme::default().style(Tile::HorizontalBeam);
+        let theme = TileTheme::default().with_style(
+            Tile::Wheel,
+            TileStyle {
+                stroke_width: 20.0,
+                ..beam
+            },
+        );
+
+        let svg = engine.to_svg_with_theme(10.0, &theme);
+        assert!(svg.contains("stroke-width:10;"));
+        assert!(svg.contains("stroke-width:2;"));
+    }
+
+    #[test]
+    fn svg_diagonal_test() {
+        let engine = engine_from_ascii("/\\");
+        let svg = engine.to_svg(10.0);
+
+        // Diagonals are lines in the beam color, moved by the margin of a single tile
+        assert!(svg.contains("stroke:#523b40;stroke-width:5;"));
+        assert!(svg.contains(r#"<line x1="10" y1="20" x2="20" y2="10"/>"#));
+        assert!(svg.contains(r#"<line x1="20" y1="10" x2="30" y2="20"/>"#));
+    }
+
+    #[test]
+    fn from_coords_test() -> Result<()> {
+        let tiles = [
+            (Coord::new(0, 0), Tile::Cross),
+            (Coord::new(3, 0), Tile::Cross),
+            (Coord::new(0, 2), Tile::Wheel),
+            (Coord::new(3, 2), Tile::Wheel),
+        ];
+        let generator = Generator::from_coords(Size::new(4, 3), &tiles, Tile::Any)?;
+        assert_eq!(*generator.grid.get_checked(Coord::new(4, 3)), Tile::Wheel);
+        assert_eq!(*generator.grid.get_checked(Coord::new(2, 2)), Tile::Any);
+
+        let outside = [(Coord::new(4, 0), Tile::Cross)];
+        assert!(Generator::from_coords(Size::new(4, 3), &outside, Tile::Any).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn to_rows_test() {
+        let engine = engine_from_ascii("+--+\n|..|\no--o");
+
+        let rows = engine.to_rows();
+        assert_eq!(rows.len(), engine.height() as usize);
+        assert!(rows.iter().all(|row| row.len() == engine.width() as usize));
+        assert_eq!(rows.concat(), *engine.tiles());
+        assert_eq!(rows[2][3], Tile::Wheel);
+    }
+
+    #[test]
+    fn engine_try_from_rows_test() -> Result<()> {
+        let engine = Engine::try_from(vec![
+            vec![Tile::Cross, Tile::HorizontalBeam, Tile::Cross],
+            vec![Tile::Wheel, Tile::Empty, Tile::Wheel],
+        ])?;
+        assert_eq!(engine.width(), 3);
+        assert_eq!(engine.height(), 2);
+        assert_eq!(engine.to_ascii(), "+-+\no o");
+
+        let engine = engine_from_ascii("+--+\n|..|\no--o");
+        assert_eq!(Engine::try_from(engine.to_rows())?.tiles(), engine.tiles());
+
+        // Rows with different lengths
+        assert!(Engine::try_from(vec![vec![Tile::Wall, Tile::Wall], vec![Tile::Wall]]).is_err());
+        // Edge tile
+        assert!(Engine::try_from(vec![vec![Tile::Edge]]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn tile_material_test() {
+        assert_eq!(Tile::Empty.material(), None);
+        assert_eq!(Tile::Any.material(), None);
+        assert_eq!(Tile::Edge.material(), None);
+        assert_eq!(Tile::Wheel.material(), Some(Material::Wood));
+        assert_eq!(Tile::Wall.material(), Some(Material::Wood));
+        assert_eq!(Tile::HorizontalBeam.material(), Some(Material::Metal));
+        assert_eq!(Tile::VerticalBeam.material(), Some(Material::Metal));
+        assert_eq!(Tile::DiagonalBeam1.material(), Some(Material::Metal));
+        assert_eq!(Tile::DiagonalBeam2.material(), Some(Material::Metal));
+        assert_eq!(Tile::Cross.material(), Some(Material::Metal));
+        assert_eq!(Tile::Joint.material(), Some(Material::Metal));
+    }
+
+    #[test]
+    fn material_thickness_test() {
+        let engine = engine_from_ascii("-|");
+        let svg = engine.to_svg_with_materials(10.0, &TileTheme::default(), |tile| {
+            if tile == Tile::HorizontalBeam {
+                Material::Rope
+            } else {
+                Material::Metal
+            }
+        });
+
+        // Rope is half as thick as wood & metal is one and a half times as thick
+        assert!(svg.contains("stroke-width:5;"));
+        assert!(svg.contains("stroke-width:15;"));
+    }
+
+    #[test]
+    fn from_reader_test() -> Result<()> {
+        let generator = Generator::from_reader(io::Cursor::new(b"+--+\n|..|\no--o"))?;
+        assert_eq!(*generator.grid.get_checked(Coord::new(1, 1)), Tile::Cross);
+
+        assert!(Generator::from_reader(io::Cursor::new(b"\xFF")).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_ascii_file_error_test() -> Result<()> {
+        let path = std::env::temp_dir().join("siege_from_ascii_file_error_test.ascii");
+        std::fs::write(&path, "+--+\n|..|\n|.x|\n+--+")?;
+
+        let result = Generator::from_ascii_file(&path);
+        std::fs::remove_file(&path)?;
+
+        let message = format!("{:#}", result.err().unwrap());
+        assert!(message.contains(&path.display().to_string()));
+        assert!(message.contains("line 3, column 3"));
+
+        assert!(Tile::try_from_ascii('x').is_err());
+        assert_eq!(Tile::try_from_ascii('o')?, Tile::Wheel);
+
+        Ok(())
+    }
+
+    #[test]
+    fn max_output_size_test() {
+        assert_eq!(
+            Generator::default().max_output_size(Size::new(10, 10)),
+            Size::new(12, 12)
+        );
+    }
+
+    #[test]
+    fn max_patterns_test() -> Result<()> {
+        let generator = Generator::from_ascii("+-+\n|o|\n+-+")?;
+        let pattern_count = generator.pattern_count();
+        assert!(pattern_count > 1);
+
+        let generator = generator.with_max_patterns(pattern_count)?;
+        assert!(generator.with_max_patterns(1).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn components_test() {
+        // The '/' doesn't connect to the beam on it's top left
+        let engine = engine_from_ascii("-- /\n  / ");
+        let (count, labels) = engine.components();
+        assert_eq!(count, 2);
+        assert_eq!(
+            labels,
+            vec![Some(0), Some(0), None, Some(1), None, None, Some(1), None,]
+        );
+    }
+
+    #[test]
+    fn generate_skeleton_with_edges_test() -> Result<()> {
+        let generator = Generator::from_ascii("+-+\n|.|\n+-+")?;
+
+        // The fixed box can only fill an output of it's own size
+        let engine = generator
+            .generate_skeleton_with_edges(3, 3, 10, &mut rand::thread_rng())
+            .unwrap();
+        assert_eq!(engine.width(), 5);
+        assert_eq!(engine.height(), 5);
+        assert_eq!(engine.to_ascii(), "~~~~~\n~+-+~\n~|.|~\n~+-+~\n~~~~~");
+
+        Ok(())
+    }
+
+    #[test]
+    fn render_test() {
+        #[derive(Default)]
+        struct CountingCanvas {
+            rects: usize,
+            lines: usize,
+            circles: usize,
+        }
+
+        impl Canvas for CountingCanvas {
+            fn fill_rect(&mut self, _tile: Tile, _x: f32, _y: f32, _width: f32, _height: f32) {
+                self.rects += 1;
+            }
+
+            fn draw_line(&mut self, _tile: Tile, _start: (f32, f32), _end: (f32, f32), _: f32) {
+                self.lines += 1;
+            }
+
+            fn draw_circle(&mut self, _tile: Tile, _center: (f32, f32), _radius: f32) {
+                self.circles += 1;
+            }
+        }
+
+        let engine = engine_from_ascii("..-+\n/o#\\");
+        let mut canvas = CountingCanvas::default();
+        engine.render(&mut canvas, 10.0);
+
+        // The walls are merged into a single rectangle, crosses & joints are two rectangles
+        assert_eq!(canvas.rects, 6);
+        assert_eq!(canvas.lines, 2);
+        assert_eq!(canvas.circles, 2);
+    }
+
+    #[test]
+    fn engine_builder_test() {
+        let engine = EngineBuilder::new(6, 5)
+            .frame(0, 0, 6, 4)
+            .diagonal_brace(1, 1, 2)
+            .beam((3, 1), (4, 2))
+            .wheel_at(1, 4)
+            .wheel_at(4, 4)
+            .build();
+
+        assert_eq!(
+            engine.to_ascii(),
+            "+----+\n|\\/\\ |\n|/\\ \\|\n+----+\n o  o "
+        );
+
+        // The beams cross the frame
+        let engine = EngineBuilder::new(3, 3)
+            .frame(0, 0, 3, 3)
+            .beam((1, 0), (1, 2))
+            .build();
+        assert_eq!(engine.to_ascii(), "+++\n|||\n+++");
+    }
+
+    #[test]
+    fn tile_spacing_test() {
+        let mut engine = engine_from_ascii("oo---o\n|    |\no-o--o");
+        assert_eq!(
+            engine.spacing_violations(Tile::Wheel, 1),
+            vec![(Coord::new(0, 0), Coord::new(1, 0))]
+        );
+        assert_eq!(engine.spacing_violations(Tile::Wheel, 2).len(), 7);
+
+        assert_eq!(engine.enforce_tile_spacing(Tile::Wheel, 1), 1);
+        assert_eq!(engine.to_ascii(), "o ---o\n|    |\no-o--o");
+        assert!(engine.spacing_violations(Tile::Wheel, 1).is_empty());
+
+        // Wheels spaced out far enough pass
+        let engine = engine_from_ascii("o-o-o");
+        assert!(engine.spacing_violations(Tile::Wheel, 1).is_empty());
+    }
+
+    #[test]
+    fn connections_test() {
+        assert_eq!(
+            Tile::HorizontalBeam.connections(),
+            Connections::EAST | Connections::WEST
+        );
+        assert!(Tile::Cross.connections().contains(Connections::NORTH));
+        assert!(!Tile::DiagonalBeam1
+            .connections()
+            .contains(Connections::NORTH_WEST));
+        assert!(Tile::Wheel.connections().is_empty());
+
+        let engine = engine_from_ascii("+--+\n|..|\n+--+");
+        assert!(engine.validate_connections().is_empty());
+
+        // The beam on the bottom right is dangling, the diagonal is connected to the cross
+        let engine = engine_from_ascii("+--+\n|./|\n+---");
+        assert_eq!(
+            engine.validate_connections(),
+            vec![(Coord::new(3, 2), Connections::EAST)]
+        );
+
+        let engine = engine_from_ascii(" / \n---");
+        assert_eq!(
+            engine.validate_connections(),
+            vec![
+                (Coord::new(1, 0), Connections::NORTH_EAST),
+                (Coord::new(0, 1), Connections::WEST),
+                (Coord::new(2, 1), Connections::EAST)
+            ]
+        );
+    }
+
+    #[test]
+    fn flood_replace_test() {
+        // The single wall on the right is separated from the block by a beam
+        let mut engine = engine_from_ascii("...- \n...|.\n---+ ");
+        assert_eq!(engine.flood_replace(1, 1, Tile::Cross), 6);
+        assert_eq!(engine.to_ascii(), "+++- \n+++|.\n---+ ");
+
+        assert_eq!(engine.flood_replace(4, 0, Tile::Wheel), 1);
+        assert_eq!(engine.flood_replace(4, 0, Tile::Wheel), 0);
+        assert_eq!(engine.flood_replace(5, 0, Tile::Wheel), 0);
+        assert_eq!(engine.flood_replace(0, 0, Tile::Edge), 0);
+        assert_eq!(engine.to_ascii(), "+++-o\n+++|.\n---+ ");
+    }
+
+    #[test]
+    fn outline_test() {
+        let engine = engine_from_ascii("...\n...");
+        assert_eq!(
+            engine.outline(),
+            vec![(0.0, 0.0), (3.0, 0.0), (3.0, 2.0), (0.0, 2.0)]
+        );
+
+        // An L shape surrounded by empty space, with a hole that's ignored
+        let engine = engine_from_ascii("     \n +-+ \n | | \n +-+-\n     ");
+        assert_eq!(
+            engine.outline(),
+            vec![
+                (1.0, 1.0),
+                (4.0, 1.0),
+                (4.0, 3.0),
+                (5.0, 3.0),
+                (5.0, 4.0),
+                (1.0, 4.0)
+            ]
+        );
+
+        // Diagonal neighbors are part of the outline
+        assert_eq!(
+            engine_from_ascii("\\ \n \\").outline(),
+            vec![
+                (0.0, 0.0),
+                (1.0, 0.0),
+                (1.0, 1.0),
+                (2.0, 1.0),
+                (2.0, 2.0),
+                (1.0, 2.0),
+                (1.0, 1.0),
+                (0.0, 1.0)
+            ]
+        );
+
+        assert!(engine_from_ascii("  \n  ").outline().is_empty());
+    }
+
+    #[test]
+    fn tile_context_test() {
+        let engine = engine_from_ascii("+--\n|..\n|  ");
+
+        // The end of the horizontal beam
+        let context = engine.tile_context(2, 0).unwrap();
+        assert_eq!(
+            context,
+            TileContext {
+                left: true,
+                down_left: true,
+                down: true,
+                ..TileContext::default()
+            }
+        );
+        assert_eq!(context.mask(), 0b0110_1000);
+        assert_eq!(context.straight_count(), 2);
+
+        // The corner
+        let context = engine.tile_context(0, 0).unwrap();
+        assert_eq!(context.mask(), 0b1101_0000);
+        assert_eq!(context.straight_count(), 2);
+
+        // The end of the vertical beam
+        assert_eq!(engine.tile_context(0, 2).unwrap().straight_count(), 1);
+
+        assert!(engine.tile_context(3, 0).is_none());
+    }
+
+    #[test]
+    fn fingerprint_test() {
+        let engine = engine_from_ascii("+--+\n|..|\no--o");
+        assert_eq!(
+            engine.fingerprint(),
+            engine_from_ascii("+--+\n|..|\no--o").fingerprint()
+        );
+        assert_eq!(engine.fingerprint(), engine.clone().fingerprint());
+
+        // A single tile changed
+        assert_ne!(
+            engine.fingerprint(),
+            engine_from_ascii("+--+\n|. |\no--o").fingerprint()
+        );
+        // Same tiles with different dimensions
+        assert_ne!(
+            engine_from_ascii("..\n..").fingerprint(),
+            engine_from_ascii("....").fingerprint()
+        );
+
+        // The fingerprint doesn't change between runs or platforms
+        assert_eq!(Engine::default().fingerprint(), 0xA8C7_F832_281A_39C5);
+    }
+
+    #[test]
+    fn degenerate_template_test() -> Result<()> {
+        // Only empty space
+        let err = Generator::from_ascii("   \n   ").err().unwrap();
+        assert_eq!(err.to_string(), "template has no structural tiles");
+        assert!(Generator::from_ascii("\n\n").is_err());
+
+        // Only walls can only generate a uniform engine of the same size
+        let generator = Generator::from_ascii("...\n...\n...")?;
+        let engine = generator
+            .generate_skeleton(3, 3, 10, &mut rand::thread_rng())
+            .unwrap();
+        assert!(engine.tiles().iter().all(|tile| *tile == Tile::Wall));
+        assert!(generator
+            .generate_skeleton(6, 6, 10, &mut rand::thread_rng())
+            .is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn patterns_test() -> Result<()> {
+        let generator = Generator::from_ascii("+-+\n|.|\n+-+")?;
+
+        let patterns = generator.patterns();
+        assert_eq!(patterns.len(), generator.pattern_count());
+        assert!(patterns
+            .iter()
+            .all(|pattern| pattern.width() == 3 && pattern.height() == 3));
+        // The pattern starting at the top left of the template
+        assert!(patterns
+            .iter()
+            .any(|pattern| pattern.to_ascii() == "+-+\n|.|\n+-+"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn pad_test() {
+        let engine = engine_from_ascii("+-+\n|.|\no-o");
+
+        let padded = engine.pad(1, 1, 1, 1);
+        assert_eq!(padded.width(), 5);
+        assert_eq!(padded.height(), 5);
+        assert_eq!(padded.to_ascii(), "     \n +-+ \n |.| \n o-o \n     ");
+
+        let padded = engine.pad(2, 0, 0, 1);
+        assert_eq!(padded.to_ascii(), "  +-+\n  |.|\n  o-o\n     ");
+        assert_eq!(padded.crop(2, 0, 3, 3).unwrap().tiles(), engine.tiles());
+
+        let padded = Engine::default().pad(1, 1, 0, 0);
+        assert_eq!((padded.width(), padded.height()), (2, 0));
+    }
+
+    #[test]
+    fn from_array_edge_test() {
+        let map = [Tile::Wall, Tile::Edge, Tile::Wall, Tile::Wall];
+        assert!(Generator::from_array(2, 2, &map).is_err());
     }
 }
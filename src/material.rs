@@ -0,0 +1,114 @@
+use crate::{Engine, Tile};
+use std::collections::VecDeque;
+
+/// What a part of the engine is made of, which determines their strength.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Material {
+    /// Only strong enough to carry itself.
+    Rope,
+    /// Carries a small tower.
+    Wood,
+    /// The strongest material.
+    Metal,
+}
+
+impl Material {
+    /// The amount of tiles a single tile of this material can carry, including itself.
+    pub fn strength(self) -> f32 {
+        match self {
+            Material::Rope => 1.0,
+            Material::Wood => 4.0,
+            Material::Metal => 16.0,
+        }
+    }
+
+    /// How thick a beam of this material is drawn, as a fraction of the tile size.
+    pub fn thickness(self) -> f32 {
+        match self {
+            Material::Rope => 0.25,
+            Material::Wood => 0.5,
+            Material::Metal => 0.75,
+        }
+    }
+}
+
+impl Tile {
+    /// The material a tile is made of by default, `None` for tiles without any structure.
+    ///
+    /// All beams are made of metal while the wheels & the walls are wooden. None of the tiles
+    /// are made of rope by default.
+    pub fn material(self) -> Option<Material> {
+        match self {
+            Tile::HorizontalBeam
+            | Tile::VerticalBeam
+            | Tile::DiagonalBeam1
+            | Tile::DiagonalBeam2
+            | Tile::Cross
+            | Tile::Joint => Some(Material::Metal),
+            Tile::Wheel | Tile::Wall => Some(Material::Wood),
+            Tile::Empty | Tile::Any | Tile::Edge => None,
+        }
+    }
+}
+
+impl Engine {
+    /// Estimate whether the engine can bear it's own weight.
+    ///
+    /// This is a simplified model where the wheels & the tiles on the bottom row rest on the
+    /// ground. Every other tile passes it's load on to the closest connected tile on the way to
+    /// the ground, every tile carries itself and all the tiles resting on it. The load a tile
+    /// carries can't be more than the strength of it's material, and every tile that isn't empty
+    /// must have a path to the ground.
+    pub fn can_bear_weight<F>(&self, material_of: F) -> bool
+    where
+        F: Fn(Tile) -> Material,
+    {
+        let width = self.width() as usize;
+
+        // The tiles resting on the ground carry the rest
+        let mut queue = self
+            .tiles()
+            .iter()
+            .enumerate()
+            .filter(|(index, tile)| {
+                !tile.is_empty()
+                    && (**tile == Tile::Wheel || index / width + 1 == self.height() as usize)
+            })
+            .map(|(index, _)| index)
+            .collect::<VecDeque<_>>();
+
+        // Find the shortest path to the ground for every tile, which is where it's load goes
+        let mut reached = vec![false; self.tiles().len()];
+        queue.iter().for_each(|index| reached[*index] = true);
+        let mut carried_by = vec![None; self.tiles().len()];
+        let mut order = Vec::with_capacity(self.tiles().len());
+        while let Some(index) = queue.pop_front() {
+            order.push(index);
+
+            for neighbor in self.connected_neighbors(index) {
+                if !reached[neighbor] {
+                    reached[neighbor] = true;
+                    carried_by[neighbor] = Some(index);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        // Floating parts can't be carried at all
+        if order.len() != self.tiles().iter().filter(|tile| !tile.is_empty()).count() {
+            return false;
+        }
+
+        // Pass the loads down, starting with the tiles furthest from the ground
+        let mut loads = vec![1.0; self.tiles().len()];
+        for index in order.iter().rev() {
+            if let Some(carrier) = carried_by[*index] {
+                loads[carrier] += loads[*index];
+            }
+        }
+
+        order
+            .iter()
+            .all(|index| loads[*index] <= material_of(self.tiles()[*index]).strength())
+    }
+}
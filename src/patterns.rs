@@ -0,0 +1,127 @@
+use crate::{Engine, Generator, Tile, PATTERN_SIZE};
+use coord_2d::Coord;
+use wfc::PatternId;
+
+/// Cardinal direction from a pattern to it's neighbor.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Direction {
+    /// The neighbor is above.
+    North,
+    /// The neighbor is to the right.
+    East,
+    /// The neighbor is below.
+    South,
+    /// The neighbor is to the left.
+    West,
+}
+
+impl Direction {
+    /// All directions, clockwise starting from the top.
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    /// The offset of the neighbor in this direction.
+    fn offset(self) -> (i32, i32) {
+        match self {
+            Direction::North => (0, -1),
+            Direction::East => (1, 0),
+            Direction::South => (0, 1),
+            Direction::West => (-1, 0),
+        }
+    }
+}
+
+impl Generator {
+    /// Summarize which patterns are allowed next to each other.
+    ///
+    /// For every pattern & direction this lists all patterns that can be placed next to it in
+    /// that direction. Two patterns are compatible when their tiles are the same where they
+    /// overlap, which is the same rule the wave function collapse uses.
+    pub fn adjacencies(&self) -> Vec<(PatternId, Direction, Vec<PatternId>)> {
+        let patterns = self.pattern_tiles();
+
+        patterns
+            .iter()
+            .enumerate()
+            .flat_map(|(pattern_id, pattern)| {
+                let patterns = &patterns;
+
+                Direction::ALL.iter().map(move |direction| {
+                    let compatible = patterns
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, other)| patterns_overlap(pattern, other, *direction))
+                        .map(|(other_id, _)| other_id as PatternId)
+                        .collect();
+
+                    (pattern_id as PatternId, *direction, compatible)
+                })
+            })
+            .collect()
+    }
+
+    /// All patterns learned from the template as small engines, indexed by pattern id.
+    ///
+    /// Every pattern is 3 by 3 tiles. The padding around the template is part of the patterns
+    /// as `Tile::Edge` tiles, which aren't drawn by the renderers.
+    pub fn patterns(&self) -> Vec<Engine> {
+        self.pattern_tiles()
+            .into_iter()
+            .map(|tiles| Engine {
+                width: PATTERN_SIZE as u32,
+                height: PATTERN_SIZE as u32,
+                tiles,
+            })
+            .collect()
+    }
+
+    /// The tiles of every pattern in row-major order, indexed by pattern id.
+    pub(crate) fn pattern_tiles(&self) -> Vec<Vec<Tile>> {
+        let id_grid = self.overlapping_patterns.id_grid_original_orientation();
+        let size = self.grid.size();
+        let num_patterns = id_grid.iter().max().map_or(0, |max| *max as usize + 1);
+
+        let mut patterns = vec![vec![]; num_patterns];
+        for (coord, pattern_id) in id_grid.enumerate() {
+            let pattern = &mut patterns[*pattern_id as usize];
+            if !pattern.is_empty() {
+                continue;
+            }
+
+            // The patterns wrap around the template
+            for y in 0..PATTERN_SIZE {
+                for x in 0..PATTERN_SIZE {
+                    let tile_coord = Coord::new(
+                        (coord.x + x) % size.width() as i32,
+                        (coord.y + y) % size.height() as i32,
+                    );
+                    pattern.push(*self.grid.get_checked(tile_coord));
+                }
+            }
+        }
+
+        patterns
+    }
+}
+
+/// Whether the other pattern can be placed next to the pattern in the direction.
+pub(crate) fn patterns_overlap(pattern: &[Tile], other: &[Tile], direction: Direction) -> bool {
+    let (dx, dy) = direction.offset();
+
+    (0..PATTERN_SIZE).all(|y| {
+        (0..PATTERN_SIZE).all(|x| {
+            let (other_x, other_y) = (x - dx, y - dy);
+            if other_x < 0 || other_y < 0 || other_x >= PATTERN_SIZE || other_y >= PATTERN_SIZE {
+                // Not overlapping
+                return true;
+            }
+
+            pattern[(y * PATTERN_SIZE + x) as usize]
+                == other[(other_y * PATTERN_SIZE + other_x) as usize]
+        })
+    })
+}
@@ -0,0 +1,142 @@
+use crate::{Engine, Generator, Material, Tile, TileTheme, TRANSPARENT};
+use anyhow::{anyhow, Result};
+use image::{RgbImage, Rgba, RgbaImage};
+use std::{collections::HashMap, f32::consts::SQRT_2, path::Path};
+
+impl Engine {
+    /// Render the engine as a pixel image.
+    ///
+    /// Every tile becomes a `scale` by `scale` block of pixels without any anti-aliasing, so the
+    /// output is suitable for pixel art. The colors used are:
+    ///
+    /// - `Wall`: `#9B4C51`
+    /// - `HorizontalBeam`, `VerticalBeam`, `DiagonalBeam1`, `DiagonalBeam2`, `Cross`, `Joint` &
+    ///   `Wheel`: `#523B40`
+    /// - Everything else is fully transparent.
+    pub fn to_image(&self, scale: u32) -> RgbaImage {
+        self.to_image_with_theme(scale, &TileTheme::default())
+    }
+
+    /// Render the engine as a pixel image with custom colors.
+    ///
+    /// Outlines aren't drawn, so tiles are filled with their stroke color when it isn't
+    /// transparent and with their fill color otherwise.
+    pub fn to_image_with_theme(&self, scale: u32, theme: &TileTheme) -> RgbaImage {
+        // Beams are half a tile thick
+        self.image(scale, theme, |_| 0.5)
+    }
+
+    /// Render the engine as a pixel image where beams are as thick as their material.
+    pub fn to_image_with_materials<F>(
+        &self,
+        scale: u32,
+        theme: &TileTheme,
+        material_of: F,
+    ) -> RgbaImage
+    where
+        F: Fn(Tile) -> Material,
+    {
+        self.image(scale, theme, |tile| material_of(tile).thickness())
+    }
+
+    /// Render the engine as a pixel image with the thickness of the beams as a fraction of a tile.
+    fn image<F>(&self, scale: u32, theme: &TileTheme, thickness_of: F) -> RgbaImage
+    where
+        F: Fn(Tile) -> f32,
+    {
+        // Neighboring wheels are merged into bigger wheels, every tile only has to check the
+        // wheels of which the bounding box overlaps it
+        let mut wheels_per_tile = vec![vec![]; self.tiles().len()];
+        for wheel in self.wheels() {
+            let (x, y) = wheel.center;
+            let start_x = (x - wheel.radius).floor().max(0.0) as u32;
+            let start_y = (y - wheel.radius).floor().max(0.0) as u32;
+            let end_x = ((x + wheel.radius).ceil() as u32).min(self.width());
+            let end_y = ((y + wheel.radius).ceil() as u32).min(self.height());
+
+            for tile_y in start_y..end_y {
+                for tile_x in start_x..end_x {
+                    wheels_per_tile[self.index(tile_x, tile_y).unwrap()].push(wheel);
+                }
+            }
+        }
+
+        RgbaImage::from_fn(self.width() * scale, self.height() * scale, |x, y| {
+            let index = self.index(x / scale, y / scale).unwrap();
+            let tile = self.tiles()[index];
+
+            // Position inside the tile, from the center of the pixel
+            let u = ((x % scale) as f32 + 0.5) / scale as f32;
+            let v = ((y % scale) as f32 + 0.5) / scale as f32;
+
+            let (tile_x, tile_y) = ((x / scale) as f32 + u, (y / scale) as f32 + v);
+            if wheels_per_tile[index]
+                .iter()
+                .any(|wheel| wheel.contains(tile_x, tile_y))
+            {
+                Rgba(theme.style(Tile::Wheel).solid())
+            } else if tile != Tile::Wheel && tile_covers(tile, u, v, thickness_of(tile)) {
+                Rgba(theme.style(tile).solid())
+            } else {
+                Rgba(TRANSPARENT)
+            }
+        })
+    }
+}
+
+impl Generator {
+    /// Use a template from a path pointing to an image file.
+    ///
+    /// Every pixel becomes a tile, the color of the pixel is mapped to a tile with the palette.
+    pub fn from_image<P>(path: P, palette: &HashMap<[u8; 3], Tile>) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::from_rgb_image(&image::open(path)?.to_rgb8(), palette)
+    }
+
+    /// Use a template from an image that's already loaded.
+    ///
+    /// Every pixel becomes a tile, the color of the pixel is mapped to a tile with the palette.
+    pub fn from_rgb_image(image: &RgbImage, palette: &HashMap<[u8; 3], Tile>) -> Result<Self> {
+        let tiles = image
+            .enumerate_pixels()
+            .map(|(x, y, pixel)| {
+                palette.get(&pixel.0).copied().ok_or_else(|| {
+                    anyhow!(
+                        "color #{:02X}{:02X}{:02X} at ({}, {}) is not in the palette",
+                        pixel[0],
+                        pixel[1],
+                        pixel[2],
+                        x,
+                        y
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Self::from_array(image.width(), image.height(), &tiles)
+    }
+}
+
+/// Whether the shape of the tile covers the point, with both coordinates in the range `0..1`.
+///
+/// The thickness of the beams is a fraction of the tile.
+pub(crate) fn tile_covers(tile: Tile, u: f32, v: f32, thickness: f32) -> bool {
+    let (start, end) = (0.5 - thickness / 2.0, 0.5 + thickness / 2.0);
+    let horizontal = (start..end).contains(&v);
+    let vertical = (start..end).contains(&u);
+
+    match tile {
+        Tile::Wall => true,
+        Tile::HorizontalBeam => horizontal,
+        Tile::VerticalBeam => vertical,
+        Tile::Cross | Tile::Joint => horizontal || vertical,
+        // Connects left bottom to right top
+        Tile::DiagonalBeam1 => (u + v - 1.0).abs() < thickness / 2.0 * SQRT_2,
+        // Connects left top to right bottom
+        Tile::DiagonalBeam2 => (u - v).abs() < thickness / 2.0 * SQRT_2,
+        Tile::Wheel => (u - 0.5).powi(2) + (v - 0.5).powi(2) <= 0.25,
+        _ => false,
+    }
+}
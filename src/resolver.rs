@@ -0,0 +1,89 @@
+use crate::{coord_is_edge, Engine, Tile, TileContext};
+use coord_2d::{Coord, Size};
+use grid_2d::Grid;
+
+/// A strategy for replacing the `Tile::Any` tiles left by the template after generating.
+///
+/// Resolvers only see which of the neighbors are structural, tiles outside of the engine are
+/// treated as empty.
+pub trait AnyResolver {
+    /// The tile an any tile with this neighborhood becomes.
+    fn resolve(&self, ctx: &TileContext) -> Tile;
+}
+
+/// The resolver used when nothing else is set, following an edge detection heuristic.
+///
+/// Tiles surrounded on all straight sides become a `Cross`, or a `Wall` when the diagonal
+/// neighbors are structural as well. Tiles between two straight neighbors become a beam in that
+/// direction, everything else becomes a `Cross`.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct DefaultAnyResolver;
+
+impl AnyResolver for DefaultAnyResolver {
+    fn resolve(&self, ctx: &TileContext) -> Tile {
+        if ctx.up && ctx.down && ctx.left && ctx.right {
+            // Check for corners
+            if !ctx.up_left || !ctx.up_right || !ctx.down_left || !ctx.down_right {
+                Tile::Cross
+            } else {
+                Tile::Wall
+            }
+        } else if ctx.up && ctx.down {
+            Tile::VerticalBeam
+        } else if ctx.left && ctx.right {
+            Tile::HorizontalBeam
+        } else {
+            Tile::Cross
+        }
+    }
+}
+
+impl Engine {
+    /// Replace all `Tile::Any` tiles with the tiles chosen by the resolver.
+    ///
+    /// All tiles are resolved from the engine as it was before, so resolved tiles don't influence
+    /// the other any tiles.
+    pub fn resolve_any_with<A>(&mut self, resolver: &A)
+    where
+        A: AnyResolver + ?Sized,
+    {
+        let width_with_edge = self.width as usize + 2;
+
+        // Pad the tiles with edges so the neighbors never have to be checked for boundaries
+        let padded = Grid::new_fn(Size::new(self.width + 2, self.height + 2), |coord| {
+            if coord_is_edge(coord, self.width as i32 + 2, self.height as i32 + 2) {
+                Tile::Edge
+            } else {
+                self.tiles[self.index(coord.x as u32 - 1, coord.y as u32 - 1).unwrap()]
+            }
+        })
+        .iter()
+        .copied()
+        .collect::<Vec<_>>();
+
+        // Reuse the buffer of the tiles
+        let height_with_edge = self.height as usize + 2;
+        self.tiles.clear();
+        self.tiles.extend(
+            padded
+                .iter()
+                .enumerate()
+                // Remove the edge padding added above
+                .filter(|(index, _)| {
+                    let coord = Coord::new(
+                        (index % width_with_edge) as i32,
+                        (index / width_with_edge) as i32,
+                    );
+
+                    !coord_is_edge(coord, width_with_edge as i32, height_with_edge as i32)
+                })
+                .map(|(index, tile)| {
+                    if *tile == Tile::Any {
+                        resolver.resolve(&TileContext::at(index, &padded, width_with_edge))
+                    } else {
+                        *tile
+                    }
+                }),
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use crate::{
+    coord_is_edge, CellPatterns, CheckedForbid, Engine, ForceBorderForbid, Generator, Tile,
+};
+use rand::Rng;
+use wfc::{wrap::WrapXY, Observe, RunOwn};
+
+/// Iterator over the intermediate states of a single collapse, see [`Generator::steps`].
+///
+/// [`Generator::steps`]: struct.Generator.html#method.steps
+pub struct CollapseIterator<'a, R: Rng> {
+    generator: &'a Generator,
+    /// `None` when the output is empty, so there's nothing to collapse.
+    run: Option<RunOwn<'a, WrapXY, ForceBorderForbid>>,
+    rng: &'a mut R,
+    width: u32,
+    height: u32,
+    done: bool,
+    contradiction: bool,
+}
+
+impl<'a, R: Rng> CollapseIterator<'a, R> {
+    /// Whether the collapse stopped because of a contradiction.
+    ///
+    /// The last snapshot is then only partially collapsed.
+    pub fn contradiction(&self) -> bool {
+        self.contradiction
+    }
+
+    /// The current state of the wave with all cells that aren't collapsed yet as `Any`.
+    fn snapshot(&self, run: &RunOwn<'a, WrapXY, ForceBorderForbid>) -> Engine {
+        let tiles = run
+            .wave_cell_ref_enumerate()
+            .filter(|(coord, _)| {
+                !coord_is_edge(*coord, self.width as i32 + 2, self.height as i32 + 2)
+            })
+            .map(|(_, cell)| match cell.chosen_pattern_id() {
+                Ok(pattern_id) => *self
+                    .generator
+                    .overlapping_patterns
+                    .pattern_top_left_value(pattern_id),
+                Err(_) => Tile::Any,
+            })
+            .collect();
+
+        Engine {
+            tiles,
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+impl<'a, R: Rng> Iterator for CollapseIterator<'a, R> {
+    type Item = Engine;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut run = self.run.take()?;
+
+        if self.contradiction {
+            // Forcing the border already failed, so there's nothing to collapse
+            self.done = true;
+
+            return Some(self.snapshot(&run));
+        }
+
+        // wfc resets the wave after a contradiction, so keep the state before the step
+        let before = run.clone();
+
+        let result = run.step(self.rng);
+        let snapshot = match result {
+            Ok(Observe::Incomplete) => self.snapshot(&run),
+            Ok(Observe::Complete) => {
+                self.done = true;
+
+                // Resolve the `Any` tiles from the template like a normal generation would
+                let mut engine = self.snapshot(&run);
+                engine.resolve_any_with(&*self.generator.any_resolver);
+
+                engine
+            }
+            Err(_) => {
+                self.done = true;
+                self.contradiction = true;
+                run = before;
+
+                self.snapshot(&run)
+            }
+        };
+        self.run = Some(run);
+
+        Some(snapshot)
+    }
+}
+
+impl Generator {
+    /// Collapse a new engine one step at a time.
+    ///
+    /// Every item is a snapshot of the engine after a single collapse step, with the tiles that
+    /// aren't decided yet shown as `Any`. A step can decide multiple tiles at once, so there are at
+    /// most as many steps as tiles. The last snapshot is the finished engine, unless the collapse
+    /// ran into a contradiction, which can be checked with [`CollapseIterator::contradiction`].
+    /// When either dimension is zero there are no steps at all.
+    ///
+    /// [`CollapseIterator::contradiction`]: struct.CollapseIterator.html#method.contradiction
+    pub fn steps<'a, R>(
+        &'a self,
+        output_width: u32,
+        output_height: u32,
+        rng: &'a mut R,
+    ) -> CollapseIterator<'a, R>
+    where
+        R: Rng,
+    {
+        let forbid = self.force_border_forbid();
+        let run = self
+            .wave_size(output_width, output_height)
+            .map(|wave_size| {
+                RunOwn::new_forbid(wave_size, &self.global_stats, forbid.clone(), rng)
+            });
+
+        // A contradiction while forbidding resets the wave without the border
+        let contradiction = run
+            .as_ref()
+            .is_some_and(|run| forbid.first_broken_rule(run).is_some());
+
+        CollapseIterator {
+            generator: self,
+            run,
+            rng,
+            width: output_width,
+            height: output_height,
+            done: false,
+            contradiction,
+        }
+    }
+}
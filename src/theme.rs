@@ -0,0 +1,101 @@
+use crate::Tile;
+use std::collections::HashMap;
+
+/// A color with red, green, blue & alpha channels.
+pub type Rgba = [u8; 4];
+
+/// Fully transparent, anything with this color isn't drawn.
+pub const TRANSPARENT: Rgba = [0, 0, 0, 0];
+
+/// Colors & outline used to draw a single tile.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TileStyle {
+    /// Color of the inside of the shape.
+    pub fill: Rgba,
+    /// Color of the outline of the shape.
+    ///
+    /// Pixel renderers don't draw outlines, they fill the whole shape with this color instead
+    /// when it's not transparent.
+    pub stroke: Rgba,
+    /// Width of the outline, the SVG renderer divides it by the scale.
+    pub stroke_width: f32,
+}
+
+impl TileStyle {
+    /// A style that doesn't draw anything.
+    pub const INVISIBLE: TileStyle = TileStyle {
+        fill: TRANSPARENT,
+        stroke: TRANSPARENT,
+        stroke_width: 0.0,
+    };
+
+    /// The color to use when the shape is drawn without an outline.
+    pub fn solid(&self) -> Rgba {
+        if self.stroke[3] > 0 {
+            self.stroke
+        } else {
+            self.fill
+        }
+    }
+}
+
+/// The colors used by all renderers for every tile.
+///
+/// The default theme uses `#9B4C51` for walls without an outline and beams outlined with
+/// `#523B40`, tiles without a style aren't drawn.
+#[derive(Debug, Clone)]
+pub struct TileTheme {
+    styles: HashMap<Tile, TileStyle>,
+}
+
+impl TileTheme {
+    /// A theme where nothing is drawn.
+    pub fn empty() -> Self {
+        Self {
+            styles: HashMap::new(),
+        }
+    }
+
+    /// Set the style of a tile.
+    pub fn with_style(mut self, tile: Tile, style: TileStyle) -> Self {
+        self.styles.insert(tile, style);
+
+        self
+    }
+
+    /// The style of a tile, invisible if none is set.
+    pub fn style(&self, tile: Tile) -> TileStyle {
+        self.styles
+            .get(&tile)
+            .copied()
+            .unwrap_or(TileStyle::INVISIBLE)
+    }
+}
+
+impl Default for TileTheme {
+    fn default() -> Self {
+        let wall = [0x9B, 0x4C, 0x51, 0xFF];
+        let beam = TileStyle {
+            fill: wall,
+            stroke: [0x52, 0x3B, 0x40, 0xFF],
+            stroke_width: 100.0,
+        };
+
+        Self::empty()
+            .with_style(
+                Tile::Wall,
+                TileStyle {
+                    fill: wall,
+                    stroke: TRANSPARENT,
+                    stroke_width: 0.0,
+                },
+            )
+            .with_style(Tile::HorizontalBeam, beam)
+            .with_style(Tile::VerticalBeam, beam)
+            .with_style(Tile::DiagonalBeam1, beam)
+            .with_style(Tile::DiagonalBeam2, beam)
+            .with_style(Tile::Cross, beam)
+            .with_style(Tile::Joint, beam)
+            .with_style(Tile::Wheel, beam)
+    }
+}
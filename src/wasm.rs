@@ -0,0 +1,30 @@
+use crate::{bytes::tiles_from_discriminants, Engine};
+use anyhow::{anyhow, Result};
+
+impl Engine {
+    /// The tiles as a flat array of their numeric values, for passing to JavaScript.
+    ///
+    /// Combined with the [`width`] & [`height`] this can be passed across the WebAssembly boundary
+    /// without any conversions, use [`from_discriminants`] to get the engine back.
+    ///
+    /// [`width`]: struct.Engine.html#method.width
+    /// [`height`]: struct.Engine.html#method.height
+    /// [`from_discriminants`]: struct.Engine.html#method.from_discriminants
+    pub fn to_discriminants(&self) -> Vec<u8> {
+        self.tiles.iter().map(|tile| tile.as_u8()).collect()
+    }
+
+    /// Reconstruct an engine from the numeric values of it's tiles.
+    pub fn from_discriminants(width: u32, height: u32, discriminants: &[u8]) -> Result<Self> {
+        // Multiply as 64 bit so big dimensions can't overflow
+        if discriminants.len() as u64 != width as u64 * height as u64 {
+            return Err(anyhow!("array doesn't match width & height"));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            tiles: tiles_from_discriminants(discriminants)?,
+        })
+    }
+}